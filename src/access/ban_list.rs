@@ -0,0 +1,133 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::access::json_list;
+
+/// `expires` of a ban that never ends.
+pub const FOREVER: &str = "forever";
+
+/// A ban in `banned-players.json` or `banned-ips.json`. Player bans with an XUID match
+/// that XUID, and also the name for clients without one, so turning off Xbox
+/// authentication never lifts a ban. IP bans match by `ip`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanEntry {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub xuid: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub ip: String,
+    /// RFC 3339 timestamps, `expires` may also be `forever`.
+    pub created: String,
+    pub source: String,
+    pub expires: String,
+    pub reason: String,
+}
+
+impl BanEntry {
+    pub fn new(source: &str, expires: Option<DateTime<Utc>>, reason: &str) -> BanEntry {
+        BanEntry {
+            name: String::new(),
+            xuid: String::new(),
+            ip: String::new(),
+            created: Utc::now().to_rfc3339(),
+            source: source.to_string(),
+            expires: expires.map_or(FOREVER.to_string(), |expires| expires.to_rfc3339()),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Unparsable expiry dates count as permanent, so a typo never lifts a ban.
+    pub fn get_expiry(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.expires).ok().map(|expires| expires.with_timezone(&Utc))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.get_expiry().is_some_and(|expires| expires <= Utc::now())
+    }
+
+    /// Whether a connecting player is banned by this entry. Unlike ops and the whitelist,
+    /// where a name must not unlock an XUID-bound entry, a ban errs on the side of denying.
+    fn matches(&self, xuid: &str, name: &str, ip: &str) -> bool {
+        if !self.ip.is_empty() {
+            return self.ip == ip;
+        }
+        if !self.xuid.is_empty() && !xuid.is_empty() {
+            return self.xuid == xuid;
+        }
+        !self.name.is_empty() && self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Whether this is the ban an admin refers to, by IP, XUID or name.
+    fn is_listed_as(&self, xuid: &str, name: &str, ip: &str) -> bool {
+        if !self.ip.is_empty() || !ip.is_empty() {
+            return self.ip == ip;
+        }
+        (!self.xuid.is_empty() && self.xuid == xuid)
+            || (!self.name.is_empty() && self.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A JSON list of bans. Expired entries are dropped whenever the list is saved.
+#[derive(Default)]
+pub struct BanList {
+    path: PathBuf,
+    entries: RwLock<Vec<BanEntry>>,
+}
+
+impl BanList {
+    pub fn load(path: &Path) -> BanList {
+        let list = BanList {
+            path: path.to_path_buf(),
+            entries: RwLock::new(Vec::new()),
+        };
+        list.reload();
+        list
+    }
+
+    pub fn reload(&self) {
+        json_list::reload(&self.path, &self.entries);
+    }
+
+    /// The active ban of a player or address, if any.
+    pub fn get(&self, xuid: &str, name: &str, ip: &str) -> Option<BanEntry> {
+        self.entries.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .find(|entry| entry.matches(xuid, name, ip) && !entry.is_expired())
+            .cloned()
+    }
+
+    pub fn get_all(&self) -> Vec<BanEntry> {
+        self.entries.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .filter(|entry| !entry.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Adds `entry`, replacing an older ban of the same target, and saves the file.
+    pub fn add(&self, entry: BanEntry) -> Result<(), String> {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        entries.retain(|existing| !existing.is_listed_as(&entry.xuid, &entry.name, &entry.ip));
+        entries.push(entry);
+        save_entries(&self.path, &mut entries)
+    }
+
+    /// Lifts the bans matching the target and saves the file. Returns `false` if there
+    /// were none.
+    pub fn remove(&self, xuid: &str, name: &str, ip: &str) -> Result<bool, String> {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        let count = entries.len();
+        entries.retain(|entry| !entry.is_listed_as(xuid, name, ip));
+        if entries.len() == count {
+            return Ok(false);
+        }
+        save_entries(&self.path, &mut entries).map(|_| true)
+    }
+}
+
+fn save_entries(path: &Path, entries: &mut Vec<BanEntry>) -> Result<(), String> {
+    entries.retain(|entry| !entry.is_expired());
+    json_list::save(path, entries)
+}
@@ -0,0 +1,32 @@
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+use log::error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads `path` into `entries`, keeping the current entries if it fails to load.
+pub(crate) fn reload<T: DeserializeOwned + Serialize>(path: &Path, entries: &RwLock<Vec<T>>) {
+    match load::<T>(path) {
+        Ok(loaded) => *entries.write().unwrap_or_else(|err| err.into_inner()) = loaded,
+        Err(err) => error!("Failed to load {}: {}", path.display(), err),
+    }
+}
+
+/// Reads a JSON array, creating an empty file when there is none.
+pub(crate) fn load<T: DeserializeOwned + Serialize>(path: &Path) -> Result<Vec<T>, String> {
+    if !path.exists() {
+        save::<T>(path, &[])?;
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content).map_err(|e| format!("Invalid list: {}", e))
+}
+
+pub(crate) fn save<T: Serialize>(path: &Path, entries: &[T]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(entries).map_err(|e| format!("Failed to serialize: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,92 @@
+pub mod ban_list;
+mod json_list;
+pub mod whitelist;
+
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::access::ban_list::{BanEntry, BanList};
+use crate::access::whitelist::Whitelist;
+
+const NOT_ALLOWED: &str = "disconnectionScreen.notAllowed";
+
+/// The whitelist and ban lists checked when a client logs in.
+#[derive(Default)]
+pub struct AccessControl {
+    whitelist_enabled: AtomicBool,
+    whitelist: Whitelist,
+    banned_players: BanList,
+    banned_ips: BanList,
+}
+
+impl AccessControl {
+    pub fn load(data_path: &str, whitelist_enabled: bool) -> AccessControl {
+        let data_path = Path::new(data_path);
+        AccessControl {
+            whitelist_enabled: AtomicBool::new(whitelist_enabled),
+            whitelist: Whitelist::load(&data_path.join("whitelist.json")),
+            banned_players: BanList::load(&data_path.join("banned-players.json")),
+            banned_ips: BanList::load(&data_path.join("banned-ips.json")),
+        }
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turns the whitelist on or off until the next restart, `white-list` in
+    /// `server.properties` decides at startup.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        self.whitelist_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn get_whitelist(&self) -> &Whitelist {
+        &self.whitelist
+    }
+
+    pub fn get_banned_players(&self) -> &BanList {
+        &self.banned_players
+    }
+
+    pub fn get_banned_ips(&self) -> &BanList {
+        &self.banned_ips
+    }
+
+    pub fn reload(&self) {
+        self.whitelist.reload();
+        self.banned_players.reload();
+        self.banned_ips.reload();
+    }
+
+    /// Checks a logging in player, returning the disconnect message when they may not
+    /// join. Operators skip the whitelist but not the bans.
+    pub fn check(&self, xuid: &str, name: &str, ip: Option<IpAddr>, operator: bool) -> Result<(), String> {
+        if let Some(ban) = self.banned_players.get(xuid, name, "") {
+            return Err(get_ban_message(&ban));
+        }
+        if let Some(ban) = ip.and_then(|ip| self.banned_ips.get("", "", &ip.to_string())) {
+            return Err(get_ban_message(&ban));
+        }
+        if self.is_whitelist_enabled() && !operator && !self.whitelist.contains(xuid, name) {
+            return Err(NOT_ALLOWED.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// The vanilla "not allowed" screen followed by the reason and when the ban ends. A `%`
+/// prefix keeps the key translatable in a message with other text.
+pub fn get_ban_message(ban: &BanEntry) -> String {
+    let expiry = ban.get_expiry();
+    if ban.reason.is_empty() && expiry.is_none() {
+        return NOT_ALLOWED.to_string();
+    }
+    let mut message = format!("%{}", NOT_ALLOWED);
+    if !ban.reason.is_empty() {
+        message.push_str(&format!("\n{}", ban.reason));
+    }
+    if let Some(expires) = expiry {
+        message.push_str(&format!("\nUntil {}", expires.format("%Y-%m-%d %H:%M UTC")));
+    }
+    message
+}
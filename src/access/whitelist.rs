@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use crate::access::json_list;
+
+/// A player in `whitelist.json`. Entries with an XUID only match that XUID, entries
+/// without one match by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub name: String,
+    #[serde(default)]
+    pub xuid: String,
+}
+
+impl WhitelistEntry {
+    /// Whether a connecting player is this entry. `LoginData` only keeps the XUID of a
+    /// chain signed by Xbox Live, so neither a name nor an unauthenticated login can claim
+    /// an XUID-bound entry.
+    fn matches(&self, xuid: &str, name: &str) -> bool {
+        if !self.xuid.is_empty() {
+            return self.xuid == xuid;
+        }
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Whether this is the entry an admin refers to, by XUID or by name.
+    fn is_listed_as(&self, xuid: &str, name: &str) -> bool {
+        (!self.xuid.is_empty() && self.xuid == xuid) || self.name.eq_ignore_ascii_case(name)
+    }
+}
+
+#[derive(Default)]
+pub struct Whitelist {
+    path: PathBuf,
+    entries: RwLock<Vec<WhitelistEntry>>,
+}
+
+impl Whitelist {
+    pub fn load(path: &Path) -> Whitelist {
+        let whitelist = Whitelist {
+            path: path.to_path_buf(),
+            entries: RwLock::new(Vec::new()),
+        };
+        whitelist.reload();
+        whitelist
+    }
+
+    pub fn reload(&self) {
+        json_list::reload(&self.path, &self.entries);
+    }
+
+    pub fn contains(&self, xuid: &str, name: &str) -> bool {
+        self.entries.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .any(|entry| entry.matches(xuid, name))
+    }
+
+    pub fn get_all(&self) -> Vec<WhitelistEntry> {
+        self.entries.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    /// Adds or updates a player and saves the file.
+    pub fn add(&self, entry: WhitelistEntry) -> Result<(), String> {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        entries.retain(|existing| !existing.is_listed_as(&entry.xuid, &entry.name));
+        entries.push(entry);
+        json_list::save(&self.path, &entries)
+    }
+
+    /// Removes a player and saves the file. Returns `false` if they were not listed.
+    pub fn remove(&self, xuid: &str, name: &str) -> Result<bool, String> {
+        let mut entries = self.entries.write().unwrap_or_else(|err| err.into_inner());
+        let count = entries.len();
+        entries.retain(|entry| !entry.is_listed_as(xuid, name));
+        if entries.len() == count {
+            return Ok(false);
+        }
+        json_list::save(&self.path, &entries).map(|_| true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XUID: &str = "2535400000000001";
+
+    fn whitelist_with(entry: WhitelistEntry) -> Whitelist {
+        let whitelist = Whitelist::default();
+        whitelist.entries.write().unwrap().push(entry);
+        whitelist
+    }
+
+    #[test]
+    fn xuid_bound_entry_needs_the_xuid() {
+        let whitelist = whitelist_with(WhitelistEntry { name: String::from("Steve"), xuid: XUID.to_string() });
+        assert!(whitelist.contains(XUID, "Steve"));
+        assert!(whitelist.contains(XUID, "Renamed"));
+
+        // Unauthenticated logins have no XUID, other accounts have their own.
+        assert!(!whitelist.contains("", "Steve"));
+        assert!(!whitelist.contains("2535400000000002", "Steve"));
+    }
+
+    #[test]
+    fn name_only_entry_matches_by_name() {
+        let whitelist = whitelist_with(WhitelistEntry { name: String::from("Steve"), xuid: String::new() });
+        assert!(whitelist.contains("", "steve"));
+        assert!(!whitelist.contains("", "Alex"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::net::IpAddr;
+use chrono::{DateTime, Duration, Utc};
+use crate::access::ban_list::BanEntry;
+use crate::access::get_ban_message;
+use crate::access::whitelist::WhitelistEntry;
+use crate::command::{Command, PermissionLevel};
+use crate::command::parameter::{CommandArgs, CommandParameter, ParameterType, TargetSelector};
+use crate::command::registry::CommandRegistry;
+use crate::command::sender::CommandSender;
+use crate::server::Server;
+
+/// Registers the whitelist and ban commands.
+pub fn register(registry: &CommandRegistry) {
+    registry.register(
+        Command::new("whitelist", "Manages the server whitelist.", PermissionLevel::Host, |server, sender, args| {
+            let access = server.get_access();
+            let whitelist = access.get_whitelist();
+            let action = args.get_string("action").unwrap_or_default();
+            match action {
+                "on" | "off" => {
+                    access.set_whitelist_enabled(action == "on");
+                    sender.send_message(server, &format!("Turned the whitelist {}", action));
+                }
+                "list" => {
+                    let mut names: Vec<String> = whitelist.get_all().into_iter().map(|entry| entry.name).collect();
+                    names.sort();
+                    sender.send_message(server, &format!("There are {} whitelisted players: {}", names.len(), names.join(", ")));
+                }
+                "reload" => {
+                    access.reload();
+                    sender.send_message(server, "Reloaded the whitelist and ban lists");
+                }
+                "add" => {
+                    let name = args.get_string("player").ok_or("Missing player")?;
+                    let xuid = server.get_sessions().get_by_name(name).map(|session| session.xuid.clone()).unwrap_or_default();
+                    whitelist.add(WhitelistEntry { name: name.to_string(), xuid })?;
+                    sender.send_message(server, &format!("Added {} to the whitelist", name));
+                }
+                "remove" => {
+                    let name = args.get_string("player").ok_or("Missing player")?;
+                    if !whitelist.remove("", name)? {
+                        return Err(format!("{} is not whitelisted", name));
+                    }
+                    sender.send_message(server, &format!("Removed {} from the whitelist", name));
+                }
+                _ => return Err(format!("Unknown action {}", action)),
+            }
+            Ok(())
+        })
+            .alias("allowlist")
+            .overload(vec![CommandParameter::new("action", ParameterType::Enum(
+                String::from("WhitelistAction"),
+                vec![String::from("on"), String::from("off"), String::from("list"), String::from("reload")],
+            ))])
+            .overload(vec![
+                CommandParameter::new("action", ParameterType::Enum(
+                    String::from("WhitelistEditAction"),
+                    vec![String::from("add"), String::from("remove")],
+                )),
+                CommandParameter::new("player", ParameterType::String),
+            ]),
+    );
+
+    registry.register(
+        Command::new("ban", "Bans a player from the server.", PermissionLevel::Host, |server, sender, args| {
+            ban_players(server, sender, args, None)
+        })
+            .overload(vec![
+                CommandParameter::new("player", ParameterType::Target),
+                CommandParameter::optional("reason", ParameterType::Message),
+            ]),
+    );
+
+    registry.register(
+        Command::new("tempban", "Bans a player for a time, such as 30m, 12h or 7d.", PermissionLevel::Host, |server, sender, args| {
+            let duration = args.get_string("duration").ok_or("Missing duration")?;
+            let expires = Utc::now() + parse_duration(duration)?;
+            ban_players(server, sender, args, Some(expires))
+        })
+            .overload(vec![
+                CommandParameter::new("player", ParameterType::Target),
+                CommandParameter::new("duration", ParameterType::String),
+                CommandParameter::optional("reason", ParameterType::Message),
+            ]),
+    );
+
+    registry.register(
+        Command::new("ban-ip", "Bans an IP address, or the address of an online player.", PermissionLevel::Host, |server, sender, args| {
+            let target = args.get_string("target").ok_or("Missing address")?;
+            let ip = match target.parse::<IpAddr>() {
+                Ok(ip) => ip,
+                Err(_) => server.get_sessions().get_by_name(target)
+                    .and_then(|session| session.address)
+                    .map(|address| address.ip())
+                    .ok_or(format!("{} is neither an IP address nor an online player", target))?,
+            };
+
+            let mut ban = BanEntry::new(sender.get_name(), None, args.get_string("reason").unwrap_or_default());
+            ban.ip = ip.to_string();
+            let message = get_ban_message(&ban);
+            server.get_access().get_banned_ips().add(ban)?;
+
+            for session in server.get_sessions().get_all() {
+                if session.address.is_some_and(|address| address.ip() == ip) {
+                    session.disconnect(&message);
+                }
+            }
+            sender.send_message(server, &format!("Banned IP address {}", ip));
+            Ok(())
+        })
+            .overload(vec![
+                CommandParameter::new("target", ParameterType::String),
+                CommandParameter::optional("reason", ParameterType::Message),
+            ]),
+    );
+
+    registry.register(
+        Command::new("pardon", "Lifts the ban of a player.", PermissionLevel::Host, |server, sender, args| {
+            let name = args.get_string("player").ok_or("Missing player")?;
+            if !server.get_access().get_banned_players().remove("", name, "")? {
+                return Err(format!("{} is not banned", name));
+            }
+            sender.send_message(server, &format!("Unbanned {}", name));
+            Ok(())
+        })
+            .alias("unban")
+            .overload(vec![CommandParameter::new("player", ParameterType::String)]),
+    );
+
+    registry.register(
+        Command::new("pardon-ip", "Lifts the ban of an IP address.", PermissionLevel::Host, |server, sender, args| {
+            let ip = args.get_string("ip").ok_or("Missing address")?;
+            let ip = ip.parse::<IpAddr>().map_err(|_| format!("Invalid IP address {}", ip))?;
+            if !server.get_access().get_banned_ips().remove("", "", &ip.to_string())? {
+                return Err(format!("{} is not banned", ip));
+            }
+            sender.send_message(server, &format!("Unbanned IP address {}", ip));
+            Ok(())
+        })
+            .alias("unban-ip")
+            .overload(vec![CommandParameter::new("ip", ParameterType::String)]),
+    );
+
+    registry.register(
+        Command::new("banlist", "Lists banned players or IP addresses.", PermissionLevel::Host, |server, sender, args| {
+            let access = server.get_access();
+            let (bans, kind) = match args.get_string("type") {
+                Some("ips") => (access.get_banned_ips().get_all(), "IP addresses"),
+                _ => (access.get_banned_players().get_all(), "players"),
+            };
+            sender.send_message(server, &format!("There are {} banned {}:", bans.len(), kind));
+            for ban in bans {
+                let target = if ban.ip.is_empty() { &ban.name } else { &ban.ip };
+                sender.send_message(server, &format!(
+                    "{} was banned by {} until {}: {}",
+                    target, ban.source, ban.expires, ban.reason,
+                ));
+            }
+            Ok(())
+        })
+            .overload(vec![CommandParameter::optional("type", ParameterType::Enum(
+                String::from("BanListType"),
+                vec![String::from("players"), String::from("ips")],
+            ))]),
+    );
+}
+
+/// Bans the players `player` refers to and kicks the ones that are online. A name of a
+/// player that is offline is banned by name.
+fn ban_players(server: &Server, sender: &CommandSender, args: &CommandArgs, expires: Option<DateTime<Utc>>) -> Result<(), String> {
+    let target = args.get_target("player").ok_or("Missing player")?;
+    let reason = args.get_string("reason").unwrap_or_default();
+    let access = server.get_access();
+    let banned_players = access.get_banned_players();
+
+    let targets = sender.resolve_target(server, target);
+    if targets.is_empty() {
+        let TargetSelector::Player(name) = target else {
+            return Err(String::from("No targets matched selector"));
+        };
+        let mut ban = BanEntry::new(sender.get_name(), expires, reason);
+        ban.name = name.clone();
+        banned_players.add(ban)?;
+        sender.send_message(server, &format!("Banned {}", name));
+        return Ok(());
+    }
+
+    for session in targets {
+        let mut ban = BanEntry::new(sender.get_name(), expires, reason);
+        ban.name = session.display_name.clone();
+        ban.xuid = session.xuid.clone();
+        let message = get_ban_message(&ban);
+        banned_players.add(ban)?;
+        session.disconnect(&message);
+        sender.send_message(server, &format!("Banned {}", session.display_name));
+    }
+    Ok(())
+}
+
+/// Parses durations such as `90s`, `30m`, `1d12h` or `2w`.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for char in text.chars() {
+        if char.is_ascii_digit() {
+            number.push(char);
+            continue;
+        }
+        let value: i64 = number.parse().map_err(|_| format!("Invalid duration {}", text))?;
+        number.clear();
+        total += match char.to_ascii_lowercase() {
+            's' => Duration::seconds(value),
+            'm' => Duration::minutes(value),
+            'h' => Duration::hours(value),
+            'd' => Duration::days(value),
+            'w' => Duration::weeks(value),
+            _ => return Err(format!("Invalid duration unit {} in {}", char, text)),
+        };
+    }
+    if !number.is_empty() || total <= Duration::zero() {
+        return Err(format!("Invalid duration {}", text));
+    }
+    Ok(total)
+}
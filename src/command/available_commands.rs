@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use bedrockrs::proto::v662::packets::AvailableCommandsPacket;
+use bedrockrs::proto::v662::types::{CommandData, CommandEnumData, CommandOverloadData, CommandParameterData};
+use crate::command::Command;
+use crate::command::parameter::ParameterType;
+use crate::command::registry::CommandRegistry;
+
+const ARG_FLAG_VALID: u32 = 0x100000;
+const ARG_FLAG_ENUM: u32 = 0x200000;
+
+// Parameter type ids of the 1.21 protocols.
+const ARG_TYPE_INT: u32 = 1;
+const ARG_TYPE_FLOAT: u32 = 3;
+const ARG_TYPE_TARGET: u32 = 8;
+const ARG_TYPE_STRING: u32 = 56;
+const ARG_TYPE_POSITION: u32 = 65;
+const ARG_TYPE_MESSAGE: u32 = 68;
+
+/// Collects enum values and enums while commands are added, so that enums used by several
+/// commands are only sent once.
+#[derive(Default)]
+struct EnumTable {
+    values: Vec<String>,
+    value_indices: HashMap<String, u32>,
+    enums: Vec<CommandEnumData>,
+    enum_indices: HashMap<String, u32>,
+}
+
+impl EnumTable {
+    fn add(&mut self, name: &str, values: &[String]) -> u32 {
+        if let Some(index) = self.enum_indices.get(name) {
+            return *index;
+        }
+        let values = values.iter()
+            .map(|value| {
+                let next = self.values.len() as u32;
+                *self.value_indices.entry(value.clone()).or_insert_with(|| {
+                    self.values.push(value.clone());
+                    next
+                })
+            })
+            .collect();
+        let index = self.enums.len() as u32;
+        self.enums.push(CommandEnumData { name: name.to_string(), values });
+        self.enum_indices.insert(name.to_string(), index);
+        index
+    }
+}
+
+/// Builds the list of the commands `can_use` lets a client see.
+pub fn build(registry: &CommandRegistry, can_use: impl Fn(&Command) -> bool) -> AvailableCommandsPacket {
+    let mut table = EnumTable::default();
+    let mut commands = Vec::new();
+
+    for command in registry.get_all() {
+        if !can_use(&command) {
+            continue;
+        }
+
+        let alias_enum = if command.aliases.is_empty() {
+            -1
+        } else {
+            let mut names = vec![command.name.clone()];
+            names.extend(command.aliases.iter().cloned());
+            table.add(&format!("{}Aliases", command.name), &names) as i32
+        };
+
+        let overloads = if command.overloads.is_empty() { vec![Vec::new()] } else { command.overloads.clone() };
+        let overloads = overloads.iter()
+            .map(|parameters| CommandOverloadData {
+                is_chaining: false,
+                parameters: parameters.iter()
+                    .map(|parameter| CommandParameterData {
+                        name: parameter.name.clone(),
+                        parse_symbol: parse_symbol(&mut table, &parameter.parameter_type),
+                        is_optional: parameter.optional,
+                        options: 0,
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        commands.push(CommandData {
+            name: command.name.clone(),
+            description: command.description.clone(),
+            flags: 0,
+            permission_level: command.permission as u8,
+            alias_enum,
+            chained_subcommand_offsets: Vec::new(),
+            overloads,
+        });
+    }
+
+    AvailableCommandsPacket {
+        enum_values: table.values,
+        chained_subcommand_values: Vec::new(),
+        postfixes: Vec::new(),
+        enums: table.enums,
+        chained_subcommands: Vec::new(),
+        commands,
+        soft_enums: Vec::new(),
+        enum_constraints: Vec::new(),
+    }
+}
+
+fn parse_symbol(table: &mut EnumTable, parameter_type: &ParameterType) -> u32 {
+    match parameter_type {
+        ParameterType::Int => ARG_FLAG_VALID | ARG_TYPE_INT,
+        ParameterType::Float => ARG_FLAG_VALID | ARG_TYPE_FLOAT,
+        ParameterType::String => ARG_FLAG_VALID | ARG_TYPE_STRING,
+        ParameterType::Message => ARG_FLAG_VALID | ARG_TYPE_MESSAGE,
+        ParameterType::Target => ARG_FLAG_VALID | ARG_TYPE_TARGET,
+        ParameterType::Position => ARG_FLAG_VALID | ARG_TYPE_POSITION,
+        ParameterType::Bool => {
+            ARG_FLAG_VALID | ARG_FLAG_ENUM | table.add("Boolean", &[String::from("true"), String::from("false")])
+        }
+        ParameterType::Enum(name, values) => ARG_FLAG_VALID | ARG_FLAG_ENUM | table.add(name, values),
+    }
+}
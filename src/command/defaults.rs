@@ -0,0 +1,198 @@
+use crate::command::{Command, PermissionLevel};
+use crate::command::parameter::{CommandParameter, ParameterType, TargetSelector};
+use crate::command::registry::CommandRegistry;
+use crate::permission::{Operator, PermissionManager};
+
+const PAGE_SIZE: usize = 8;
+
+/// Registers the commands every server has.
+pub fn register(registry: &CommandRegistry) {
+    registry.register(
+        Command::new("help", "Lists commands or shows the usage of one.", PermissionLevel::Any, |server, sender, args| {
+            if let Some(name) = args.get_string("command") {
+                let Some(command) = server.get_commands().get(name).filter(|command| sender.can_use(server, command)) else {
+                    return Err(format!("Unknown command: {}", name));
+                };
+                sender.send_message(server, &format!("§e/{}: §f{}", command.name, command.description));
+                for usage in command.get_usage() {
+                    sender.send_message(server, &usage);
+                }
+                return Ok(());
+            }
+
+            let commands: Vec<_> = server.get_commands().get_all().into_iter()
+                .filter(|command| sender.can_use(server, command))
+                .collect();
+            let pages = commands.len().div_ceil(PAGE_SIZE).max(1);
+            let page = args.get_int("page").unwrap_or(1).clamp(1, pages as i32) as usize;
+            sender.send_message(server, &format!("§2--- Showing help page {} of {} ---", page, pages));
+            for command in commands.iter().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE) {
+                sender.send_message(server, &format!("§e/{}: §f{}", command.name, command.description));
+            }
+            Ok(())
+        })
+            .alias("?")
+            .overload(vec![CommandParameter::optional("page", ParameterType::Int)])
+            .overload(vec![CommandParameter::new("command", ParameterType::String)]),
+    );
+
+    registry.register(
+        Command::new("stop", "Stops the server.", PermissionLevel::Owner, |server, sender, _| {
+            sender.send_message(server, "Stopping the server...");
+            server.shutdown(&format!("Stopped by {}", sender.get_name()));
+            Ok(())
+        }),
+    );
+
+    registry.register(
+        Command::new("list", "Lists the players on the server.", PermissionLevel::Any, |server, sender, _| {
+            let mut names: Vec<String> = server.get_sessions().get_all().iter()
+                .map(|session| session.display_name.clone())
+                .collect();
+            names.sort();
+            sender.send_message(server, &format!(
+                "There are {}/{} players online:",
+                names.len(), server.get_properties().max_players,
+            ));
+            sender.send_message(server, &names.join(", "));
+            Ok(())
+        }),
+    );
+
+    registry.register(
+        Command::new("kick", "Kicks a player from the server.", PermissionLevel::GameDirectors, |server, sender, args| {
+            let target = args.get_target("player").ok_or("Missing player")?;
+            let reason = args.get_string("reason").unwrap_or("Kicked by an operator").to_string();
+            let targets = sender.resolve_target(server, target);
+            if targets.is_empty() {
+                return Err(String::from("No targets matched selector"));
+            }
+            for session in targets {
+                session.disconnect(&reason);
+                sender.send_message(server, &format!("Kicked {} from the game: '{}'", session.display_name, reason));
+            }
+            Ok(())
+        })
+            .overload(vec![
+                CommandParameter::new("player", ParameterType::Target),
+                CommandParameter::optional("reason", ParameterType::Message),
+            ]),
+    );
+
+    registry.register(
+        Command::new("say", "Sends a message in the chat to other players.", PermissionLevel::GameDirectors, |server, sender, args| {
+            let message = format!("[{}] {}", sender.get_name(), args.get_string("message").unwrap_or_default());
+            server.get_sessions().broadcast_message(&message);
+            server.print(&message);
+            Ok(())
+        })
+            .overload(vec![CommandParameter::new("message", ParameterType::Message)]),
+    );
+
+    registry.register(
+        Command::new("op", "Grants operator status to a player.", PermissionLevel::Host, |server, sender, args| {
+            let target = args.get_target("player").ok_or("Missing player")?;
+            let targets = sender.resolve_target(server, target);
+            let permissions = server.get_permissions();
+            // Nobody can hand out a higher level than their own.
+            let sender_level = sender.get_permission_level(server);
+            let level = server.get_properties().get_op_permission_level().min(sender_level) as u8;
+
+            // Players that are offline are added by name only.
+            if let (true, TargetSelector::Player(name)) = (targets.is_empty(), target) {
+                if get_listed_level(&permissions, "", name) > sender_level {
+                    return Err(format!("{} has a higher permission level than you", name));
+                }
+                permissions.add_operator(Operator { name: name.clone(), xuid: String::new(), level })?;
+                sender.send_message(server, &format!("Opped: {}", name));
+                return Ok(());
+            }
+            if targets.is_empty() {
+                return Err(String::from("No targets matched selector"));
+            }
+            for session in targets {
+                if get_listed_level(&permissions, &session.xuid, &session.display_name) > sender_level {
+                    sender.send_message(server, &format!("§c{} has a higher permission level than you", session.display_name));
+                    continue;
+                }
+                permissions.add_operator(Operator {
+                    name: session.display_name.clone(),
+                    xuid: session.xuid.clone(),
+                    level,
+                })?;
+                server.refresh_permissions(&session);
+                session.send_message("§eYou are now an operator");
+                sender.send_message(server, &format!("Opped: {}", session.display_name));
+            }
+            Ok(())
+        })
+            .overload(vec![CommandParameter::new("player", ParameterType::Target)]),
+    );
+
+    registry.register(
+        Command::new("deop", "Revokes operator status from a player.", PermissionLevel::Host, |server, sender, args| {
+            let target = args.get_target("player").ok_or("Missing player")?;
+            let targets = sender.resolve_target(server, target);
+            let permissions = server.get_permissions();
+            let sender_level = sender.get_permission_level(server);
+
+            if let (true, TargetSelector::Player(name)) = (targets.is_empty(), target) {
+                if get_listed_level(&permissions, "", name) > sender_level {
+                    return Err(format!("{} has a higher permission level than you", name));
+                }
+                if !permissions.remove_operator("", name)? {
+                    return Err(format!("{} is not an operator", name));
+                }
+                sender.send_message(server, &format!("De-opped: {}", name));
+                return Ok(());
+            }
+            for session in targets {
+                if get_listed_level(&permissions, &session.xuid, &session.display_name) > sender_level {
+                    sender.send_message(server, &format!("§c{} has a higher permission level than you", session.display_name));
+                    continue;
+                }
+                if !permissions.remove_operator(&session.xuid, &session.display_name)? {
+                    sender.send_message(server, &format!("§c{} is not an operator", session.display_name));
+                    continue;
+                }
+                server.refresh_permissions(&session);
+                session.send_message("§eYou are no longer an operator");
+                sender.send_message(server, &format!("De-opped: {}", session.display_name));
+            }
+            Ok(())
+        })
+            .overload(vec![CommandParameter::new("player", ParameterType::Target)]),
+    );
+
+    registry.register(
+        Command::new("permission", "Reloads ops.json and permissions.yml.", PermissionLevel::Owner, |server, sender, _| {
+            server.get_permissions().reload();
+            for session in server.get_sessions().get_all() {
+                server.refresh_permissions(&session);
+            }
+            sender.send_message(server, "Reloaded permissions");
+            Ok(())
+        })
+            .overload(vec![CommandParameter::new("action", ParameterType::Enum(String::from("PermissionAction"), vec![String::from("reload")]))]),
+    );
+
+    registry.register(
+        Command::new("plugins", "Lists the plugins on the server.", PermissionLevel::Any, |server, sender, _| {
+            let plugins = server.get_plugins();
+            let names: Vec<String> = plugins.get_manifests().iter()
+                .map(|manifest| {
+                    let color = if plugins.is_enabled(&manifest.name) { "§a" } else { "§c" };
+                    format!("{}{}§r", color, manifest.get_full_name())
+                })
+                .collect();
+            sender.send_message(server, &format!("Plugins ({}): {}", names.len(), names.join(", ")));
+            Ok(())
+        })
+            .alias("pl"),
+    );
+}
+
+/// Level of the `ops.json` entry for a player, whether or not it would match them at login.
+fn get_listed_level(permissions: &PermissionManager, xuid: &str, name: &str) -> PermissionLevel {
+    permissions.find_operator(xuid, name).map_or(PermissionLevel::Any, |operator| operator.get_permission_level())
+}
@@ -0,0 +1,101 @@
+pub mod access;
+pub mod available_commands;
+pub mod defaults;
+pub mod parameter;
+pub mod registry;
+pub mod sender;
+
+use crate::command::parameter::{CommandArgs, CommandParameter};
+use crate::command::sender::CommandSender;
+use crate::server::Server;
+
+/// Bedrock's command permission levels, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PermissionLevel {
+    Any = 0,
+    GameDirectors = 1,
+    Admin = 2,
+    Host = 3,
+    Owner = 4,
+}
+
+impl PermissionLevel {
+    /// Levels above `Owner` are clamped to it.
+    pub fn from_u8(level: u8) -> PermissionLevel {
+        match level {
+            0 => PermissionLevel::Any,
+            1 => PermissionLevel::GameDirectors,
+            2 => PermissionLevel::Admin,
+            3 => PermissionLevel::Host,
+            _ => PermissionLevel::Owner,
+        }
+    }
+}
+
+/// Runs a parsed command. An `Err` is sent back to the sender as the failure message.
+pub type CommandExecutor = Box<dyn Fn(&mut Server, &CommandSender, &CommandArgs) -> Result<(), String> + Send + Sync>;
+
+pub struct Command {
+    pub name: String,
+    pub description: String,
+    pub aliases: Vec<String>,
+    pub permission: PermissionLevel,
+    /// Tried in order, the first one whose parameters parse is executed.
+    pub overloads: Vec<Vec<CommandParameter>>,
+    executor: CommandExecutor,
+}
+
+impl Command {
+    pub fn new<F>(name: &str, description: &str, permission: PermissionLevel, executor: F) -> Command
+    where
+        F: Fn(&mut Server, &CommandSender, &CommandArgs) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Command {
+            name: name.to_lowercase(),
+            description: description.to_string(),
+            aliases: Vec::new(),
+            permission,
+            overloads: Vec::new(),
+            executor: Box::new(executor),
+        }
+    }
+
+    pub fn alias(mut self, alias: &str) -> Command {
+        self.aliases.push(alias.to_lowercase());
+        self
+    }
+
+    pub fn overload(mut self, parameters: Vec<CommandParameter>) -> Command {
+        self.overloads.push(parameters);
+        self
+    }
+
+    /// Usage lines for every overload, `<required>` and `[optional]`.
+    pub fn get_usage(&self) -> Vec<String> {
+        if self.overloads.is_empty() {
+            return vec![format!("/{}", self.name)];
+        }
+        self.overloads.iter()
+            .map(|parameters| {
+                let mut usage = format!("/{}", self.name);
+                for parameter in parameters {
+                    if parameter.optional {
+                        usage.push_str(&format!(" [{}]", parameter.name));
+                    } else {
+                        usage.push_str(&format!(" <{}>", parameter.name));
+                    }
+                }
+                usage
+            })
+            .collect()
+    }
+
+    /// Node that lets players below `permission` use the command anyway.
+    pub fn get_permission_node(&self) -> String {
+        format!("powercrabx.command.{}", self.name)
+    }
+
+    pub fn execute(&self, server: &mut Server, sender: &CommandSender, args: &CommandArgs) -> Result<(), String> {
+        (self.executor)(server, sender, args)
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+/// Parameter types a command overload can declare. The client uses them for its
+/// suggestions, the server parses arguments with them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterType {
+    Int,
+    Float,
+    Bool,
+    /// A single word, or a quoted string.
+    String,
+    /// Everything up to the end of the line, only valid as the last parameter.
+    Message,
+    /// A player name or a target selector such as `@a`.
+    Target,
+    /// Three coordinates, each absolute or relative with `~`.
+    Position,
+    /// One of a fixed set of values, named so the client can share it between commands.
+    Enum(String, Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandParameter {
+    pub name: String,
+    pub parameter_type: ParameterType,
+    pub optional: bool,
+}
+
+impl CommandParameter {
+    pub fn new(name: &str, parameter_type: ParameterType) -> CommandParameter {
+        CommandParameter { name: name.to_string(), parameter_type, optional: false }
+    }
+
+    pub fn optional(name: &str, parameter_type: ParameterType) -> CommandParameter {
+        CommandParameter { name: name.to_string(), parameter_type, optional: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorKind {
+    AllPlayers,
+    AllEntities,
+    NearestPlayer,
+    RandomPlayer,
+    Executor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetSelector {
+    Player(String),
+    Selector(SelectorKind),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinate {
+    pub value: f32,
+    pub relative: bool,
+}
+
+impl Coordinate {
+    pub fn resolve(&self, origin: f32) -> f32 {
+        if self.relative { origin + self.value } else { self.value }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub x: Coordinate,
+    pub y: Coordinate,
+    pub z: Coordinate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    String(String),
+    Target(TargetSelector),
+    Position(Position),
+    Enum(String),
+}
+
+/// Arguments parsed for the overload that matched, by parameter name.
+#[derive(Debug, Default)]
+pub struct CommandArgs {
+    pub overload: usize,
+    values: HashMap<String, Argument>,
+}
+
+impl CommandArgs {
+    pub fn get(&self, name: &str) -> Option<&Argument> {
+        self.values.get(name)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            Argument::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn get_float(&self, name: &str) -> Option<f32> {
+        match self.get(name)? {
+            Argument::Float(value) => Some(*value),
+            Argument::Int(value) => Some(*value as f32),
+            _ => None,
+        }
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            Argument::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// String, message and enum arguments.
+    pub fn get_string(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            Argument::String(value) | Argument::Enum(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_target(&self, name: &str) -> Option<&TargetSelector> {
+        match self.get(name)? {
+            Argument::Target(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    pub fn get_position(&self, name: &str) -> Option<&Position> {
+        match self.get(name)? {
+            Argument::Position(position) => Some(position),
+            _ => None,
+        }
+    }
+}
+
+/// Splits a command line into words. Double quotes group words, `\"` escapes a quote.
+pub fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut has_token = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' if quoted && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => {
+                quoted = !quoted;
+                has_token = true;
+            }
+            char if char.is_whitespace() && !quoted => {
+                if has_token || !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            char => current.push(char),
+        }
+    }
+    if has_token || !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// Parses `tokens` against one overload.
+pub fn parse_overload(parameters: &[CommandParameter], tokens: &[String], overload: usize) -> Result<CommandArgs, String> {
+    let mut args = CommandArgs { overload, values: HashMap::new() };
+    let mut index = 0;
+
+    for parameter in parameters {
+        if index >= tokens.len() {
+            if parameter.optional {
+                break;
+            }
+            return Err(format!("Missing argument <{}>", parameter.name));
+        }
+
+        let argument = match &parameter.parameter_type {
+            ParameterType::Message => {
+                let message = tokens[index..].join(" ");
+                index = tokens.len();
+                Argument::String(message)
+            }
+            ParameterType::Position => {
+                if index + 3 > tokens.len() {
+                    return Err(format!("Expected x y z for <{}>", parameter.name));
+                }
+                let position = Position {
+                    x: parse_coordinate(&tokens[index])?,
+                    y: parse_coordinate(&tokens[index + 1])?,
+                    z: parse_coordinate(&tokens[index + 2])?,
+                };
+                index += 3;
+                Argument::Position(position)
+            }
+            parameter_type => {
+                let argument = parse_token(parameter_type, &tokens[index])
+                    .map_err(|err| format!("{} for <{}>", err, parameter.name))?;
+                index += 1;
+                argument
+            }
+        };
+        args.values.insert(parameter.name.clone(), argument);
+    }
+
+    if index < tokens.len() {
+        return Err(format!("Unexpected argument \"{}\"", tokens[index]));
+    }
+    Ok(args)
+}
+
+fn parse_token(parameter_type: &ParameterType, token: &str) -> Result<Argument, String> {
+    match parameter_type {
+        ParameterType::Int => token.parse().map(Argument::Int).map_err(|_| format!("\"{}\" is not a whole number", token)),
+        ParameterType::Float => token.parse().map(Argument::Float).map_err(|_| format!("\"{}\" is not a number", token)),
+        ParameterType::Bool => match token.to_lowercase().as_str() {
+            "true" => Ok(Argument::Bool(true)),
+            "false" => Ok(Argument::Bool(false)),
+            _ => Err(format!("\"{}\" is not true or false", token)),
+        },
+        ParameterType::String => Ok(Argument::String(token.to_string())),
+        ParameterType::Target => parse_target(token).map(Argument::Target),
+        ParameterType::Enum(_, values) => values.iter()
+            .find(|value| value.eq_ignore_ascii_case(token))
+            .map(|value| Argument::Enum(value.clone()))
+            .ok_or_else(|| format!("\"{}\" is not one of {}", token, values.join(", "))),
+        ParameterType::Message | ParameterType::Position => unreachable!("parsed from multiple tokens"),
+    }
+}
+
+fn parse_target(token: &str) -> Result<TargetSelector, String> {
+    let Some(selector) = token.strip_prefix('@') else {
+        return Ok(TargetSelector::Player(token.to_string()));
+    };
+    let kind = match selector {
+        "a" => SelectorKind::AllPlayers,
+        "e" => SelectorKind::AllEntities,
+        "p" => SelectorKind::NearestPlayer,
+        "r" => SelectorKind::RandomPlayer,
+        "s" => SelectorKind::Executor,
+        _ => return Err(format!("Unknown selector \"{}\"", token)),
+    };
+    Ok(TargetSelector::Selector(kind))
+}
+
+fn parse_coordinate(token: &str) -> Result<Coordinate, String> {
+    let (relative, value) = match token.strip_prefix('~') {
+        Some(offset) => (true, offset),
+        None => (false, token),
+    };
+    let value = if relative && value.is_empty() {
+        0.0
+    } else {
+        value.parse().map_err(|_| format!("\"{}\" is not a coordinate", token))?
+    };
+    Ok(Coordinate { value, relative })
+}
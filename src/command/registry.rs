@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use log::warn;
+use crate::command::Command;
+use crate::command::parameter::{parse_overload, tokenize, CommandArgs};
+use crate::command::sender::CommandSender;
+use crate::server::Server;
+
+/// Commands by name and alias. Shared with the console thread for tab completion, so the
+/// maps sit behind locks that are never held while a command runs.
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: RwLock<BTreeMap<String, Arc<Command>>>,
+    aliases: RwLock<BTreeMap<String, String>>,
+}
+
+impl CommandRegistry {
+    /// Adds `command`. Names win over aliases, a name or alias that is already taken is
+    /// logged and left to the command registered first.
+    pub fn register(&self, command: Command) {
+        let mut commands = self.commands.write().unwrap_or_else(|err| err.into_inner());
+        let mut aliases = self.aliases.write().unwrap_or_else(|err| err.into_inner());
+
+        if commands.contains_key(&command.name) {
+            warn!("Command /{} is already registered", command.name);
+            return;
+        }
+        aliases.remove(&command.name);
+        for alias in &command.aliases {
+            if commands.contains_key(alias) || aliases.contains_key(alias) {
+                warn!("Alias /{} of /{} is already taken", alias, command.name);
+                continue;
+            }
+            aliases.insert(alias.clone(), command.name.clone());
+        }
+        commands.insert(command.name.clone(), Arc::new(command));
+    }
+
+    pub fn unregister(&self, name: &str) -> Option<Arc<Command>> {
+        let mut commands = self.commands.write().unwrap_or_else(|err| err.into_inner());
+        let mut aliases = self.aliases.write().unwrap_or_else(|err| err.into_inner());
+
+        let command = commands.remove(&name.to_lowercase())?;
+        aliases.retain(|_, target| *target != command.name);
+        Some(command)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<Command>> {
+        let name = name.to_lowercase();
+        let name = self.aliases.read().unwrap_or_else(|err| err.into_inner()).get(&name).cloned().unwrap_or(name);
+        self.commands.read().unwrap_or_else(|err| err.into_inner()).get(&name).cloned()
+    }
+
+    /// Every command, sorted by name.
+    pub fn get_all(&self) -> Vec<Arc<Command>> {
+        self.commands.read().unwrap_or_else(|err| err.into_inner()).values().cloned().collect()
+    }
+
+    /// Command names and aliases, for completion.
+    pub fn get_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.commands.read().unwrap_or_else(|err| err.into_inner()).keys().cloned().collect();
+        names.extend(self.aliases.read().unwrap_or_else(|err| err.into_inner()).keys().cloned());
+        names.sort();
+        names
+    }
+}
+
+/// Parses and runs a command line, with or without the leading `/`. Failures are sent
+/// back to `sender`.
+pub fn dispatch(server: &mut Server, sender: &CommandSender, line: &str) {
+    let tokens = tokenize(line.trim().trim_start_matches('/'));
+    let Some((name, tokens)) = tokens.split_first() else {
+        return;
+    };
+
+    let Some(command) = server.get_commands().get(name) else {
+        sender.send_message(server, &format!("§cUnknown command: {}. Please check that the command exists and you have permission to use it.", name));
+        return;
+    };
+    if !sender.can_use(server, &command) {
+        sender.send_message(server, "§cYou do not have permission to use this command.");
+        return;
+    }
+
+    let args = match parse_arguments(&command, tokens) {
+        Ok(args) => args,
+        Err(err) => {
+            sender.send_message(server, &format!("§c{}", err));
+            for usage in command.get_usage() {
+                sender.send_message(server, &format!("§cUsage: {}", usage));
+            }
+            return;
+        }
+    };
+
+    if let Err(err) = command.execute(server, sender, &args) {
+        sender.send_message(server, &format!("§c{}", err));
+    }
+}
+
+/// Tries every overload in order and reports the error of the first one when none match.
+fn parse_arguments(command: &Command, tokens: &[String]) -> Result<CommandArgs, String> {
+    if command.overloads.is_empty() {
+        return if tokens.is_empty() {
+            Ok(CommandArgs::default())
+        } else {
+            Err(format!("Unexpected argument \"{}\"", tokens[0]))
+        };
+    }
+
+    let mut first_error = None;
+    for (index, parameters) in command.overloads.iter().enumerate() {
+        match parse_overload(parameters, tokens, index) {
+            Ok(args) => return Ok(args),
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    Err(first_error.unwrap_or_default())
+}
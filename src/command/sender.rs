@@ -0,0 +1,76 @@
+use std::sync::Arc;
+use rand_core::{OsRng, RngCore};
+use crate::command::{Command, PermissionLevel};
+use crate::command::parameter::{SelectorKind, TargetSelector};
+use crate::network::session_registry::SessionHandle;
+use crate::server::Server;
+
+/// Who ran a command.
+#[derive(Debug, Clone)]
+pub enum CommandSender {
+    Console,
+    Player(Arc<SessionHandle>),
+}
+
+impl CommandSender {
+    pub fn get_name(&self) -> &str {
+        match self {
+            CommandSender::Console => "Server",
+            CommandSender::Player(session) => &session.display_name,
+        }
+    }
+
+    pub fn get_permission_level(&self, server: &Server) -> PermissionLevel {
+        match self {
+            CommandSender::Console => PermissionLevel::Owner,
+            CommandSender::Player(session) => server.get_permission_level(session),
+        }
+    }
+
+    /// The console holds every node.
+    pub fn has_permission(&self, server: &Server, node: &str) -> bool {
+        match self {
+            CommandSender::Console => true,
+            CommandSender::Player(session) => server.has_permission(session, node),
+        }
+    }
+
+    /// Whether the sender's level reaches the command's or they hold its node.
+    pub fn can_use(&self, server: &Server, command: &Command) -> bool {
+        self.get_permission_level(server) >= command.permission
+            || self.has_permission(server, &command.get_permission_node())
+    }
+
+    /// Sends command feedback, `§` formatting codes are kept for clients and turned into
+    /// ANSI colours on the console.
+    pub fn send_message(&self, server: &Server, message: &str) {
+        match self {
+            CommandSender::Console => server.print(message),
+            CommandSender::Player(session) => {
+                session.send_message(message);
+            }
+        }
+    }
+
+    /// Online players a target argument refers to. Entity selectors only cover players
+    /// until the server tracks other entities.
+    pub fn resolve_target(&self, server: &Server, target: &TargetSelector) -> Vec<Arc<SessionHandle>> {
+        let sessions = server.get_sessions();
+        match target {
+            TargetSelector::Player(name) => sessions.get_by_name(name).into_iter().collect(),
+            TargetSelector::Selector(SelectorKind::AllPlayers | SelectorKind::AllEntities) => sessions.get_all(),
+            TargetSelector::Selector(SelectorKind::Executor | SelectorKind::NearestPlayer) => match self {
+                CommandSender::Player(session) => vec![session.clone()],
+                CommandSender::Console => Vec::new(),
+            },
+            TargetSelector::Selector(SelectorKind::RandomPlayer) => {
+                let all = sessions.get_all();
+                if all.is_empty() {
+                    return Vec::new();
+                }
+                let index = OsRng.next_u32() as usize % all.len();
+                vec![all[index].clone()]
+            }
+        }
+    }
+}
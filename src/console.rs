@@ -0,0 +1,270 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use log::{error, info, warn, Record};
+use log4rs::append::Append;
+use log4rs::config::{Deserialize, Deserializers};
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::writer::ansi::AnsiWriter;
+use log4rs::encode::writer::simple::SimpleWriter;
+use log4rs::encode::{Encode, EncoderConfig};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, ExternalPrinter, Helper};
+use tokio::sync::mpsc;
+use crate::command::registry::CommandRegistry;
+use crate::network::session_registry::SessionRegistry;
+
+/// Reads command lines from stdin on its own thread and hands them to the main tick.
+pub struct Console {
+    ansi: bool,
+    lines: mpsc::UnboundedReceiver<String>,
+}
+
+impl Console {
+    /// Starts the reader thread. History is kept in `history_path`, completion offers
+    /// command names and the names of online players.
+    pub fn start(sessions: Arc<SessionRegistry>, commands: Arc<CommandRegistry>, history_path: String, ansi: bool) -> Console {
+        let (sender, lines) = mpsc::unbounded_channel();
+
+        let spawned = thread::Builder::new()
+            .name(String::from("console"))
+            .spawn(move || read_lines(sessions, commands, history_path, ansi, sender));
+        if let Err(err) = spawned {
+            error!("Failed to start the console: {}", err);
+        }
+
+        Console { ansi, lines }
+    }
+
+    pub fn is_ansi(&self) -> bool {
+        self.ansi
+    }
+
+    /// Lines entered since the last tick.
+    pub fn take_lines(&mut self) -> Vec<String> {
+        let mut lines = Vec::new();
+        while let Ok(line) = self.lines.try_recv() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Logs `text`, turning `§` formatting codes into ANSI colours or dropping them. With
+    /// the `prompt_console` appender the line is printed above the prompt.
+    pub fn print(&self, text: &str) {
+        info!("{}", format_codes(text, self.ansi));
+    }
+}
+
+/// Prints above the prompt while the console thread is reading a line. Writing to stdout
+/// directly would garble the prompt and the input, the terminal is in raw mode.
+static PRINTER: Mutex<Option<Box<dyn ExternalPrinter + Send>>> = Mutex::new(None);
+
+fn print_line(line: &str) -> io::Result<()> {
+    let mut printer = PRINTER.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(printer) = printer.as_mut() {
+        if printer.print(line.trim_end_matches(['\r', '\n']).to_string()).is_ok() {
+            return Ok(());
+        }
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(line.as_bytes())?;
+    stdout.flush()
+}
+
+/// Log appender for the `prompt_console` kind in `log4rs.yaml`. Like log4rs' `console`
+/// appender, but lines go through the console's printer so they end up above the prompt.
+#[derive(Debug)]
+pub struct PromptConsoleAppender {
+    encoder: Box<dyn Encode>,
+    ansi: bool,
+}
+
+impl Append for PromptConsoleAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        let line = if self.ansi {
+            let mut writer = AnsiWriter(Vec::new());
+            self.encoder.encode(&mut writer, record)?;
+            writer.0
+        } else {
+            let mut writer = SimpleWriter(Vec::new());
+            self.encoder.encode(&mut writer, record)?;
+            writer.0
+        };
+        print_line(&String::from_utf8_lossy(&line))?;
+        Ok(())
+    }
+
+    fn flush(&self) {}
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptConsoleAppenderConfig {
+    encoder: Option<EncoderConfig>,
+    #[serde(default = "default_ansi")]
+    ansi: bool,
+}
+
+fn default_ansi() -> bool {
+    true
+}
+
+pub struct PromptConsoleAppenderDeserializer;
+
+impl Deserialize for PromptConsoleAppenderDeserializer {
+    type Trait = dyn Append;
+    type Config = PromptConsoleAppenderConfig;
+
+    fn deserialize(&self, config: PromptConsoleAppenderConfig, deserializers: &Deserializers) -> anyhow::Result<Box<dyn Append>> {
+        let encoder = match config.encoder {
+            Some(encoder) => deserializers.deserialize(&encoder.kind, encoder.config)?,
+            None => Box::<PatternEncoder>::default(),
+        };
+        Ok(Box::new(PromptConsoleAppender { encoder, ansi: config.ansi }))
+    }
+}
+
+fn read_lines(sessions: Arc<SessionRegistry>, commands: Arc<CommandRegistry>, history_path: String, ansi: bool, sender: mpsc::UnboundedSender<String>) {
+    let mut editor = match Editor::<ConsoleHelper, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            error!("Failed to open the console: {}", err);
+            return;
+        }
+    };
+    editor.set_helper(Some(ConsoleHelper { sessions, commands }));
+    let _ = editor.load_history(&history_path);
+    match editor.create_external_printer() {
+        Ok(printer) => *PRINTER.lock().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(printer)),
+        Err(err) => warn!("Console output may overwrite the prompt: {}", err),
+    }
+
+    read_loop(&mut editor, &history_path, ansi, &sender);
+    PRINTER.lock().unwrap_or_else(|err| err.into_inner()).take();
+}
+
+fn read_loop(editor: &mut Editor<ConsoleHelper, DefaultHistory>, history_path: &str, ansi: bool, sender: &mpsc::UnboundedSender<String>) {
+    let prompt = if ansi { "\x1b[1;33m>\x1b[0m " } else { "> " };
+    loop {
+        match editor.readline(prompt) {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let _ = editor.add_history_entry(line);
+                if let Err(err) = editor.append_history(history_path) {
+                    warn!("Failed to save console history: {}", err);
+                }
+                if sender.send(line.to_string()).is_err() {
+                    return;
+                }
+            }
+            // The terminal is in raw mode while reading, so Ctrl+C arrives here instead of
+            // as a signal.
+            Err(ReadlineError::Interrupted) => {
+                let _ = sender.send(String::from("stop"));
+                return;
+            }
+            Err(ReadlineError::Eof) => return,
+            Err(err) => {
+                error!("Console read failed: {}", err);
+                return;
+            }
+        }
+    }
+}
+
+/// Replaces Minecraft `§` formatting codes with ANSI escape sequences, or strips them
+/// when `ansi` is off.
+pub fn format_codes(text: &str, ansi: bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    let mut formatted = false;
+    while let Some(char) = chars.next() {
+        if char != '§' {
+            output.push(char);
+            continue;
+        }
+        let Some(code) = chars.next() else {
+            break;
+        };
+        if !ansi {
+            continue;
+        }
+        let escape = match code.to_ascii_lowercase() {
+            '0' => "\x1b[0;30m",
+            '1' => "\x1b[0;34m",
+            '2' => "\x1b[0;32m",
+            '3' => "\x1b[0;36m",
+            '4' => "\x1b[0;31m",
+            '5' => "\x1b[0;35m",
+            '6' => "\x1b[0;33m",
+            '7' => "\x1b[0;37m",
+            '8' => "\x1b[0;90m",
+            '9' => "\x1b[0;94m",
+            'a' => "\x1b[0;92m",
+            'b' => "\x1b[0;96m",
+            'c' => "\x1b[0;91m",
+            'd' => "\x1b[0;95m",
+            'e' => "\x1b[0;93m",
+            'f' => "\x1b[0;97m",
+            'l' => "\x1b[1m",
+            'o' => "\x1b[3m",
+            'n' => "\x1b[4m",
+            'm' => "\x1b[9m",
+            'r' => "\x1b[0m",
+            _ => continue,
+        };
+        output.push_str(escape);
+        formatted = true;
+    }
+    if formatted {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+struct ConsoleHelper {
+    sessions: Arc<SessionRegistry>,
+    commands: Arc<CommandRegistry>,
+}
+
+impl Completer for ConsoleHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(' ').map_or(0, |index| index + 1);
+        let word = line[start..].to_lowercase();
+
+        let candidates: Vec<String> = if start == 0 {
+            self.commands.get_names()
+        } else {
+            self.sessions.get_all().iter().map(|session| session.display_name.clone()).collect()
+        };
+
+        let mut matches: Vec<Pair> = candidates.into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&word))
+            .map(|candidate| Pair { display: candidate.clone(), replacement: candidate })
+            .collect();
+        matches.sort_by(|a, b| a.display.cmp(&b.display));
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for ConsoleHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ConsoleHelper {}
+
+impl Validator for ConsoleHelper {}
+
+impl Helper for ConsoleHelper {}
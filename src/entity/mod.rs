@@ -1,3 +1,11 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+static NEXT_ENTITY_ID: AtomicI64 = AtomicI64::new(1);
+
 pub struct Entity {
 
-}
\ No newline at end of file
+}
+
+pub fn next_entity_id() -> i64 {
+    NEXT_ENTITY_ID.fetch_add(1, Ordering::Relaxed)
+}
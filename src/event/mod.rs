@@ -0,0 +1,126 @@
+pub mod network;
+pub mod player;
+pub mod server;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Something listeners can observe and change. Cancellable events override the two
+/// cancel methods, firing code reads `is_cancelled` back once every listener ran.
+pub trait Event: Any + Send {
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    /// Does nothing for events that cannot be cancelled.
+    fn set_cancelled(&mut self, _cancelled: bool) {}
+}
+
+/// Order listeners run in, `Lowest` first. `Monitor` listeners run last and should only
+/// observe the outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventPriority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    Highest,
+    Monitor,
+}
+
+type Listener = Arc<dyn Fn(&mut dyn Any) + Send + Sync>;
+
+struct RegisteredListener {
+    id: u64,
+    priority: EventPriority,
+    ignore_cancelled: bool,
+    listener: Listener,
+}
+
+/// Listeners by event type. Shared between the tick and the session tasks, events are
+/// fired from both. The lock is released before listeners run, so a listener may
+/// subscribe or fire other events.
+#[derive(Default)]
+pub struct EventBus {
+    next_id: AtomicU64,
+    listeners: RwLock<HashMap<TypeId, Vec<RegisteredListener>>>,
+}
+
+impl EventBus {
+    /// Adds a listener for `E`. Listeners of the same priority run in the order they
+    /// subscribed. Returns an ID for `unsubscribe`.
+    pub fn subscribe<E, F>(&self, priority: EventPriority, listener: F) -> u64
+    where
+        E: Event,
+        F: Fn(&mut E) + Send + Sync + 'static,
+    {
+        self.add_listener::<E, F>(priority, false, listener)
+    }
+
+    /// Like `subscribe`, but the listener is skipped while the event is cancelled.
+    pub fn subscribe_uncancelled<E, F>(&self, priority: EventPriority, listener: F) -> u64
+    where
+        E: Event,
+        F: Fn(&mut E) + Send + Sync + 'static,
+    {
+        self.add_listener::<E, F>(priority, true, listener)
+    }
+
+    fn add_listener<E, F>(&self, priority: EventPriority, ignore_cancelled: bool, listener: F) -> u64
+    where
+        E: Event,
+        F: Fn(&mut E) + Send + Sync + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let listener: Listener = Arc::new(move |event: &mut dyn Any| {
+            if let Some(event) = event.downcast_mut::<E>() {
+                listener(event);
+            }
+        });
+
+        let mut listeners = self.listeners.write().unwrap_or_else(|err| err.into_inner());
+        let listeners = listeners.entry(TypeId::of::<E>()).or_default();
+        let index = listeners.partition_point(|registered| registered.priority <= priority);
+        listeners.insert(index, RegisteredListener { id, priority, ignore_cancelled, listener });
+        id
+    }
+
+    /// Removes a listener. Returns `false` if the ID was unknown.
+    pub fn unsubscribe(&self, id: u64) -> bool {
+        let mut listeners = self.listeners.write().unwrap_or_else(|err| err.into_inner());
+        for registered in listeners.values_mut() {
+            if let Some(index) = registered.iter().position(|registered| registered.id == id) {
+                registered.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether anything listens to `E`, so hot paths can skip building the event.
+    pub fn has_listeners<E: Event>(&self) -> bool {
+        self.listeners.read().unwrap_or_else(|err| err.into_inner())
+            .get(&TypeId::of::<E>())
+            .is_some_and(|listeners| !listeners.is_empty())
+    }
+
+    /// Runs every listener of `E` and hands the event back.
+    pub fn fire<E: Event>(&self, mut event: E) -> E {
+        let listeners: Vec<(bool, Listener)> = match self.listeners.read().unwrap_or_else(|err| err.into_inner()).get(&TypeId::of::<E>()) {
+            Some(listeners) => listeners.iter()
+                .map(|registered| (registered.ignore_cancelled, registered.listener.clone()))
+                .collect(),
+            None => return event,
+        };
+
+        for (ignore_cancelled, listener) in listeners {
+            if ignore_cancelled && event.is_cancelled() {
+                continue;
+            }
+            listener(&mut event);
+        }
+        event
+    }
+}
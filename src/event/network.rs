@@ -0,0 +1,52 @@
+use crate::event::Event;
+use crate::network::protocol::packet::Packet;
+
+/// Fired in the session task for every packet a client sent, before it is handled.
+/// Cancelling it drops the packet.
+#[derive(Debug)]
+pub struct PacketReceiveEvent {
+    pub entity_id: i64,
+    pub packet: Packet,
+    cancelled: bool,
+}
+
+impl PacketReceiveEvent {
+    pub fn new(entity_id: i64, packet: Packet) -> PacketReceiveEvent {
+        PacketReceiveEvent { entity_id, packet, cancelled: false }
+    }
+}
+
+impl Event for PacketReceiveEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// Fired in the session task for every packet about to be sent. Cancelling it drops the
+/// packet, changes to `packet` are sent.
+#[derive(Debug)]
+pub struct PacketSendEvent {
+    pub entity_id: i64,
+    pub packet: Packet,
+    cancelled: bool,
+}
+
+impl PacketSendEvent {
+    pub fn new(entity_id: i64, packet: Packet) -> PacketSendEvent {
+        PacketSendEvent { entity_id, packet, cancelled: false }
+    }
+}
+
+impl Event for PacketSendEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
@@ -0,0 +1,100 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use crate::event::Event;
+use crate::network::session_registry::SessionHandle;
+
+/// Fired in the session task once a login passed authentication and the ban and
+/// whitelist checks. Cancelling it disconnects the client with `kick_message`.
+#[derive(Debug)]
+pub struct PlayerPreLoginEvent {
+    pub xuid: String,
+    pub display_name: String,
+    pub address: Option<SocketAddr>,
+    pub kick_message: String,
+    cancelled: bool,
+}
+
+impl PlayerPreLoginEvent {
+    pub fn new(xuid: String, display_name: String, address: Option<SocketAddr>) -> PlayerPreLoginEvent {
+        PlayerPreLoginEvent {
+            xuid,
+            display_name,
+            address,
+            kick_message: String::from("disconnectionScreen.notAllowed"),
+            cancelled: false,
+        }
+    }
+}
+
+impl Event for PlayerPreLoginEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+/// Fired on the tick when a player spawned. An empty `join_message` is not broadcast.
+#[derive(Debug)]
+pub struct PlayerJoinEvent {
+    pub player: Arc<SessionHandle>,
+    pub join_message: String,
+}
+
+impl Event for PlayerJoinEvent {}
+
+/// Fired on the tick when an in-game player disconnected. An empty `quit_message` is not
+/// broadcast.
+#[derive(Debug)]
+pub struct PlayerQuitEvent {
+    pub player: Arc<SessionHandle>,
+    pub quit_message: String,
+}
+
+impl Event for PlayerQuitEvent {}
+
+/// Fired on the tick for a chat message. `format` receives the player name and the
+/// message in place of the two `{}`.
+#[derive(Debug)]
+pub struct PlayerChatEvent {
+    pub player: Arc<SessionHandle>,
+    pub message: String,
+    pub format: String,
+    cancelled: bool,
+}
+
+impl PlayerChatEvent {
+    pub fn new(player: Arc<SessionHandle>, message: String) -> PlayerChatEvent {
+        PlayerChatEvent {
+            player,
+            message,
+            format: String::from("<{}> {}"),
+            cancelled: false,
+        }
+    }
+
+    /// Fills the placeholders of `format` in one pass, so a `{}` inside the name or the
+    /// message is never replaced itself.
+    pub fn get_formatted(&self) -> String {
+        let mut values = [self.player.display_name.as_str(), self.message.as_str()].into_iter();
+        let mut parts = self.format.split("{}");
+        let mut formatted = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            formatted.push_str(values.next().unwrap_or("{}"));
+            formatted.push_str(part);
+        }
+        formatted
+    }
+}
+
+impl Event for PlayerChatEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
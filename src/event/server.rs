@@ -0,0 +1,10 @@
+use crate::event::Event;
+
+/// Fired at the end of every tick, after session events, console commands and scheduled
+/// tasks ran.
+#[derive(Debug)]
+pub struct ServerTickEvent {
+    pub tick: i64,
+}
+
+impl Event for ServerTickEvent {}
@@ -1,5 +1,46 @@
 mod position;
 
+use std::fs;
+use std::path::Path;
+use bedrockrs::proto::v662::enums::{Difficulty, GameType};
+use serde_yaml::Value;
+use vek::Vec3;
+use crate::server_properties::ServerProperties;
+use crate::utils::config::{Config, ConfigType};
+
 pub struct Level {
+    pub name: String,
+    pub seed: u64,
+    pub game_type: GameType,
+    pub difficulty: Difficulty,
+    pub spawn_position: Vec3<i32>,
+    pub time: i64,
+}
+
+impl Level {
+    pub fn new(properties: &ServerProperties) -> Level {
+        Level {
+            name: properties.level_name.clone(),
+            seed: properties.level_seed,
+            game_type: properties.get_game_type(),
+            difficulty: properties.get_difficulty(),
+            spawn_position: Vec3::new(0, 6, 0),
+            time: 0,
+        }
+    }
+
+    /// Writes the level's settings to `<worlds_path>/<name>/level.yml`.
+    pub fn save(&self, worlds_path: &Path) -> Result<(), String> {
+        let level_path = worlds_path.join(&self.name);
+        fs::create_dir_all(&level_path).map_err(|err| format!("Failed to create {:?}: {}", level_path, err))?;
 
-}
\ No newline at end of file
+        let mut config = Config::new(Some(level_path.join("level.yml").to_string_lossy().to_string()), ConfigType::Yaml);
+        config.set("name", Value::String(self.name.clone()));
+        config.set("seed", Value::Number(self.seed.into()));
+        config.set("time", Value::Number(self.time.into()));
+        config.set("spawn-x", Value::Number(self.spawn_position.x.into()));
+        config.set("spawn-y", Value::Number(self.spawn_position.y.into()));
+        config.set("spawn-z", Value::Number(self.spawn_position.z.into()));
+        Ok(config.save()?)
+    }
+}
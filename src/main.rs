@@ -1,13 +1,22 @@
+mod access;
+mod command;
+mod console;
 mod entity;
+mod event;
 mod level;
+mod scheduler;
 mod server;
+mod server_properties;
 mod utils;
 mod network;
+mod permission;
+mod plugin;
 mod resource_pack;
 
 use std::collections::HashMap;
 use std::env;
 use std::ops::Deref;
+use std::path::Path;
 use std::process::exit;
 use std::sync::Arc;
 use std::thread::spawn;
@@ -16,7 +25,7 @@ use bedrockrs::addon::resource::ResourcePack;
 use bedrockrs::proto::connection::Connection;
 use bedrockrs::proto::listener::Listener;
 use bedrockrs::proto::compression::Compression;
-use tokio::time::Instant;
+use tokio::time::{Duration, Instant};
 use uuid::Uuid;
 use vek::{Vec2, Vec3};
 use bedrockrs::proto::v662::enums::{Difficulty, Dimension, EditorWorldType, EducationEditionOffer, GamePublishSetting, GameType, Gamemode, GeneratorType, PacketCompressionAlgorithm, PlayerPermissionLevel, ServerAuthMovementMode};
@@ -39,10 +48,13 @@ use bedrockrs::proto::v776::helper::ProtoHelperV776;
 use bedrockrs::proto::v776::packets::{ItemRegistryPacket, StartGamePacket};
 use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
+use crate::console::{Console, PromptConsoleAppenderDeserializer};
 use crate::server::Server;
-use log::{error, info};
+use log::{error, info, warn};
 use log4rs;
+use log4rs::config::Deserializers;
 use crate::network::Network;
+use crate::resource_pack::encryption::encrypt_pack;
 use crate::utils::sem_version::SemVersion;
 
 async fn test() {
@@ -68,11 +80,19 @@ static TITLE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(true));
 static ANSI: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(true));
 
 fn main() {
-    log4rs::init_file("log4rs.yaml", Default::default())
+    let mut deserializers = Deserializers::default();
+    deserializers.insert("prompt_console", PromptConsoleAppenderDeserializer);
+    log4rs::init_file("log4rs.yaml", deserializers)
         .unwrap_or_else(|err| {
             eprintln!("Failed to initialize log4rs: {}", err);
             exit(1);
         });
+
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("encrypt-pack") {
+        exit(encrypt_pack_command(&args[2..]));
+    }
+
     let data_path = env::current_dir().unwrap().to_string_lossy().to_string();
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -84,25 +104,89 @@ fn main() {
             exit(1)
         });
 
-    runtime.block_on(async {
+    let exit_code = runtime.block_on(async {
         if *TITLE.lock().await {
             info!("Starting PowerCrabX...")
         }
 
-        let server = Arc::new(Mutex::new(Server::default()));
+        let mut server = Server::new(&data_path, &data_path).await;
+        server.set_console(Console::start(
+            server.get_sessions(),
+            server.get_commands(),
+            format!("{}/console_history.txt", data_path),
+            *ANSI.lock().await,
+        ));
 
-        Network::new(server.lock().await).await;
+        Network::new(&server).await;
 
-        server.lock().await.start().await.unwrap_or_else(|err| {
-            error!("{}", err);
+        let context = server.get_context();
+        tokio::spawn(async move {
+            let signal = wait_for_signal().await;
+            context.request_shutdown(&format!("Received {}", signal));
+            wait_for_signal().await;
+            warn!("Received a second signal, exiting without saving");
+            exit(130);
         });
 
+        let exit_code = match server.start().await {
+            Ok(()) => 0,
+            Err(err) => {
+                error!("{}", err);
+                1
+            }
+        };
+
         if *TITLE.lock().await {
             info!("Stopping PowerCrabX...")
         }
-        info!("Stopped.")
+        info!("Stopped.");
+        exit_code
     });
 
+    runtime.shutdown_timeout(Duration::from_secs(1));
+    exit(exit_code);
+
     // println!("Server started ", server);
 
 }
+
+async fn wait_for_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => tokio::select! {
+                _ = tokio::signal::ctrl_c() => "SIGINT",
+                _ = terminate.recv() => "SIGTERM",
+            },
+            Err(err) => {
+                warn!("Failed to listen for SIGTERM: {}", err);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl+C"
+    }
+}
+
+fn encrypt_pack_command(args: &[String]) -> i32 {
+    let (Some(input), Some(output)) = (args.first(), args.get(1)) else {
+        eprintln!("Usage: PowerCrabX encrypt-pack <pack folder> <output.zip> [key]");
+        return 1;
+    };
+
+    match encrypt_pack(Path::new(input), Path::new(output), args.get(2).cloned()) {
+        Ok(key) => {
+            info!("Encrypted {} to {} with key {}", input, output, key);
+            0
+        }
+        Err(err) => {
+            error!("Failed to encrypt {}: {}", input, err);
+            1
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use bedrockrs::proto::connection::Connection;
+use bedrockrs::proto::listener::Listener;
+use bedrockrs::proto::v786::helper::ProtoHelperV786;
+use log::{info, warn};
+use crate::network::advertisement::PongData;
+use crate::network::connection::bedrock_session::BedrockSession;
+use crate::server::ServerContext;
+
+const MIN_ACCEPT_BACKOFF: Duration = Duration::from_millis(10);
+const MAX_ACCEPT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// The part of a listener the accept loop drives. Implemented by the RakNet listener and
+/// by the fault injecting listener in the tests.
+pub(crate) trait Acceptor: Send + 'static {
+    type Connection: Send + 'static;
+
+    /// Waits for the next connection. After an error the loop tries again, backing off
+    /// while errors repeat.
+    fn accept(&mut self) -> impl Future<Output = Result<Self::Connection, String>> + Send;
+
+    fn update_pong(&mut self, pong_data: &PongData);
+
+    /// Runs a connection until it closes. Called on a task of its own, so a connection
+    /// that fails or panics does not affect the others.
+    fn serve(connection: Self::Connection, context: ServerContext) -> impl Future<Output = ()> + Send + 'static;
+}
+
+impl Acceptor for Listener {
+    type Connection = Connection<ProtoHelperV786>;
+
+    async fn accept(&mut self) -> Result<Self::Connection, String> {
+        let mut conn: Connection<ProtoHelperV786> = Listener::accept(self).await
+            .map_err(|err| format!("Failed to accept connection: {:?}", err))?;
+        let ip_address = conn.get_ip_address().await
+            .map_err(|err| format!("Dropping connection without an address: {:?}", err))?;
+        info!("Connection from {}", ip_address);
+        Ok(conn)
+    }
+
+    fn update_pong(&mut self, pong_data: &PongData) {
+        self.set_motd(
+            pong_data.motd.clone(),
+            pong_data.sub_motd.clone(),
+            pong_data.player_count,
+            pong_data.max_players,
+            format!("{:?}", pong_data.game_mode),
+        );
+    }
+
+    async fn serve(connection: Self::Connection, context: ServerContext) {
+        let mut bedrock_session = BedrockSession::new(connection, context);
+        bedrock_session.start().await;
+    }
+}
+
+/// Accepts connections until the server shuts down. Failed accepts are skipped, while
+/// they keep failing back to back the loop backs off so a broken or closed listener
+/// cannot spin, and only every power of two failures is logged.
+pub(crate) async fn run<A: Acceptor>(mut acceptor: A, context: ServerContext, address: SocketAddr) {
+    let mut pong = context.advertisement.subscribe();
+    let mut shutdown = context.subscribe_shutdown();
+    let mut failures: u32 = 0;
+    acceptor.update_pong(&pong.borrow_and_update().clone());
+
+    loop {
+        tokio::select! {
+            res = acceptor.accept() => {
+                let connection = match res {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        failures = failures.saturating_add(1);
+                        if failures.is_power_of_two() {
+                            warn!("{} on {} ({} in a row)", err, address, failures);
+                        }
+                        tokio::select! {
+                            _ = tokio::time::sleep(get_backoff(failures)) => continue,
+                            _ = shutdown.wait_for(Option::is_some) => break,
+                        }
+                    }
+                };
+                failures = 0;
+                tokio::spawn(A::serve(connection, context.clone()));
+            }
+            Ok(()) = pong.changed() => {
+                let pong_data = pong.borrow_and_update().clone();
+                acceptor.update_pong(&pong_data);
+            }
+            _ = shutdown.wait_for(Option::is_some) => break,
+        }
+    }
+    info!("Stopped listening on {}", address);
+}
+
+/// Delay before the next accept after `failures` failed ones in a row.
+fn get_backoff(failures: u32) -> Duration {
+    MIN_ACCEPT_BACKOFF
+        .saturating_mul(1 << failures.saturating_sub(1).min(16))
+        .min(MAX_ACCEPT_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+    use crate::server::Server;
+
+    enum Fault {
+        /// The client goes away during the login handshake.
+        DropsMidHandshake,
+        /// The connection's task panics.
+        Panics,
+    }
+
+    struct FakeConnection {
+        id: u32,
+        fault: Option<Fault>,
+        served: mpsc::UnboundedSender<u32>,
+    }
+
+    /// Hands out the scripted accept results, then waits forever like an idle listener.
+    struct FakeAcceptor {
+        script: mpsc::UnboundedReceiver<Result<FakeConnection, String>>,
+    }
+
+    impl Acceptor for FakeAcceptor {
+        type Connection = FakeConnection;
+
+        async fn accept(&mut self) -> Result<FakeConnection, String> {
+            match self.script.recv().await {
+                Some(result) => result,
+                None => std::future::pending().await,
+            }
+        }
+
+        fn update_pong(&mut self, _pong_data: &PongData) {}
+
+        async fn serve(connection: FakeConnection, _context: ServerContext) {
+            match connection.fault {
+                Some(Fault::DropsMidHandshake) => {
+                    tokio::task::yield_now().await;
+                }
+                Some(Fault::Panics) => panic!("connection {} failed", connection.id),
+                None => {
+                    let _ = connection.served.send(connection.id);
+                }
+            }
+        }
+    }
+
+    fn connection(id: u32, fault: Option<Fault>, served: &mpsc::UnboundedSender<u32>) -> Result<FakeConnection, String> {
+        Ok(FakeConnection { id, fault, served: served.clone() })
+    }
+
+    #[tokio::test]
+    async fn keeps_accepting_after_faults() {
+        let server = Server::default();
+        let (script, script_receiver) = mpsc::unbounded_channel();
+        let (served, mut served_receiver) = mpsc::unbounded_channel();
+        let address: SocketAddr = "127.0.0.1:19132".parse().unwrap();
+        let task = tokio::spawn(run(FakeAcceptor { script: script_receiver }, server.get_context(), address));
+
+        script.send(connection(1, None, &served)).unwrap();
+        assert_eq!(timeout(Duration::from_secs(5), served_receiver.recv()).await.unwrap(), Some(1));
+
+        for _ in 0..5 {
+            script.send(Err(String::from("Failed to accept connection: injected"))).unwrap();
+        }
+        script.send(connection(2, Some(Fault::DropsMidHandshake), &served)).unwrap();
+        script.send(connection(3, Some(Fault::Panics), &served)).unwrap();
+        script.send(Err(String::from("Dropping connection without an address: injected"))).unwrap();
+        script.send(connection(4, None, &served)).unwrap();
+
+        assert_eq!(timeout(Duration::from_secs(5), served_receiver.recv()).await.unwrap(), Some(4));
+        assert!(!task.is_finished());
+
+        server.get_context().request_shutdown("test");
+        timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn stops_while_backing_off() {
+        let server = Server::default();
+        let (script, script_receiver) = mpsc::unbounded_channel();
+        let address: SocketAddr = "127.0.0.1:19132".parse().unwrap();
+        for _ in 0..20 {
+            script.send(Err(String::from("listener closed"))).unwrap();
+        }
+        let task = tokio::spawn(run(FakeAcceptor { script: script_receiver }, server.get_context(), address));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        server.get_context().request_shutdown("test");
+        timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn backoff_grows_and_is_capped() {
+        assert_eq!(get_backoff(1), MIN_ACCEPT_BACKOFF);
+        assert_eq!(get_backoff(2), MIN_ACCEPT_BACKOFF * 2);
+        assert!(get_backoff(5) > get_backoff(4));
+        assert_eq!(get_backoff(u32::MAX), MAX_ACCEPT_BACKOFF);
+    }
+}
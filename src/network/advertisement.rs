@@ -0,0 +1,63 @@
+use bedrockrs::proto::v662::enums::Gamemode;
+use tokio::sync::watch;
+use crate::server_properties::ServerProperties;
+
+#[derive(Debug, Clone)]
+pub struct PongData {
+    pub motd: String,
+    pub sub_motd: String,
+    pub game_mode: Gamemode,
+    pub player_count: u32,
+    pub max_players: u32,
+}
+
+/// Shared RakNet advertisement. Listeners subscribe to it and refresh their pong
+/// data whenever it changes.
+pub struct Advertisement {
+    sender: watch::Sender<PongData>,
+}
+
+impl Advertisement {
+    pub fn new(properties: &ServerProperties) -> Advertisement {
+        let (sender, _) = watch::channel(PongData {
+            motd: properties.motd.clone(),
+            sub_motd: properties.sub_motd.clone(),
+            game_mode: properties.get_player_gamemode(),
+            player_count: 0,
+            max_players: properties.max_players,
+        });
+        Advertisement { sender }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<PongData> {
+        self.sender.subscribe()
+    }
+
+    pub fn get(&self) -> PongData {
+        self.sender.borrow().clone()
+    }
+
+    pub fn set_motd(&self, motd: String) {
+        self.sender.send_modify(|data| data.motd = motd);
+    }
+
+    pub fn set_sub_motd(&self, sub_motd: String) {
+        self.sender.send_modify(|data| data.sub_motd = sub_motd);
+    }
+
+    pub fn set_game_mode(&self, game_mode: Gamemode) {
+        self.sender.send_modify(|data| data.game_mode = game_mode);
+    }
+
+    pub fn set_max_players(&self, max_players: u32) {
+        self.sender.send_modify(|data| data.max_players = max_players);
+    }
+
+    pub fn player_joined(&self) {
+        self.sender.send_modify(|data| data.player_count += 1);
+    }
+
+    pub fn player_left(&self) {
+        self.sender.send_modify(|data| data.player_count = data.player_count.saturating_sub(1));
+    }
+}
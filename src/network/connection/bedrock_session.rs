@@ -1,46 +1,32 @@
-use crate::network::process::bedrock_session_handler::{login_handler, resource_pack_chunk_request_handler, resource_pack_handler};
+use crate::network::process::bedrock_session_handler::{login_handler, pre_spawn_handler, resource_pack_chunk_request_handler, resource_pack_handler};
 use crate::network::process::bedrock_session_handler::session_start;
 use crate::network::process::bedrock_session_handler::client_to_server_handler;
 use bedrockrs::proto::compression::Compression;
 use bedrockrs::proto::connection::Connection;
 use bedrockrs::proto::encryption::Encryption;
-use bedrockrs::proto::error::ConnectionError::TransportError;
-use bedrockrs::proto::error::{ConnectionError, TransportLayerError};
-use bedrockrs::proto::v662::enums::{
-    Difficulty, Dimension, EditorWorldType,
-    EducationEditionOffer, GamePublishSetting, GameType, Gamemode, GeneratorType
-    , PlayerPermissionLevel, ServerAuthMovementMode,
-};
-use bedrockrs::proto::v662::packets::LevelChunkPacket;
-use bedrockrs::proto::v662::types::{
-    ActorRuntimeID, ActorUniqueID, GameRulesChangedPacketData, NetworkBlockPosition
-    , SyncedPlayerMovementSettings,
-};
-use bedrockrs::proto::v729::packets::play_status::PlayStatusPacket;
-use bedrockrs::proto::v729::types::base_game_version::BaseGameVersion;
-use bedrockrs::proto::v729::types::chat_restriction_level::ChatRestrictionLevel;
-use bedrockrs::proto::v729::types::chunk_pos::ChunkPos;
-use bedrockrs::proto::v729::types::edu_shared_uri_resource::EduSharedResourceUri;
-use bedrockrs::proto::v729::types::experiments::Experiments;
-use bedrockrs::proto::v729::types::network_permissions::NetworkPermissions;
-use bedrockrs::proto::v729::types::play_status::PlayStatusType;
-use bedrockrs::proto::v729::types::spawn_biome_type::SpawnBiomeType;
-use bedrockrs::proto::v729::types::spawn_settings::SpawnSettings;
-use bedrockrs::proto::v748::packets::{
-    AttributeData, ResourcePackStackPacket,
-    UpdateAttributesPacket,
-};
-use bedrockrs::proto::v748::types::LevelSettings;
-use bedrockrs::proto::v766::enums::PlayerListPacketType;
-use bedrockrs::proto::v766::packets::{PlayerListPacket, ResourcePacksInfoPacket};
-use bedrockrs::proto::v776::packets::{ItemRegistryPacket, StartGamePacket};
-use bedrockrs::proto::v785::gamepackets::GamePackets;
-use bedrockrs::proto::v785::helper::ProtoHelperV785;
-use bedrockrs::proto::ProtoHelper;
-use std::collections::HashMap;
-use tokio::time::Instant;
-use uuid::Uuid;
-use vek::{Vec2, Vec3};
+use bedrockrs::proto::v662::enums::ConnectionFailReason;
+use bedrockrs::proto::v748::packets::{DisconnectPacket, DisconnectPacketMessage};
+use bedrockrs::proto::v786::helper::ProtoHelperV786;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use log::{debug, info, warn};
+use tokio::sync::mpsc;
+use tokio::time::{sleep_until, Instant};
+use crate::command::PermissionLevel;
+use crate::entity::next_entity_id;
+use crate::event::Event;
+use crate::event::network::{PacketReceiveEvent, PacketSendEvent};
+use crate::level::Level;
+use crate::network::connection::session_connection::SessionConnection;
+use crate::network::connection::session_error::SessionError;
+use crate::network::login_data::LoginData;
+use crate::network::protocol::packet::Packet;
+use crate::network::protocol::ProtocolVersion;
+use crate::network::session_registry::{SessionCommand, SessionEvent, SessionHandle};
+use crate::resource_pack::resource_pack_manager::ResourcePackManager;
+use crate::server::ServerContext;
+use crate::server_properties::ServerProperties;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum SessionState {
@@ -53,66 +39,334 @@ pub enum SessionState {
     Death
 }
 
+impl SessionState {
+    /// Whether a client in this state may send `packet`. Anything else counts towards
+    /// the session's unexpected packet limit. Packets the neutral model does not cover are
+    /// accepted once the client logged in, vanilla clients send some of them while joining,
+    /// such as `ClientCacheStatus` and `ServerboundLoadingScreen`.
+    pub fn allows(&self, packet: &Packet) -> bool {
+        match self {
+            SessionState::Start => matches!(packet, Packet::RequestNetworkSettings(_)),
+            SessionState::Login => matches!(packet, Packet::Login(_)),
+            SessionState::Encryption => matches!(
+                packet,
+                Packet::ClientToServerHandshake(_) | Packet::Unknown(_)
+            ),
+            SessionState::ResourcePack => matches!(
+                packet,
+                Packet::ResourcePackClientResponse(_) | Packet::ResourcePackChunkRequest(_) | Packet::Unknown(_)
+            ),
+            SessionState::PreSpawn => matches!(
+                packet,
+                Packet::RequestChunkRadius(_)
+                    | Packet::SetLocalPlayerAsInitialized(_)
+                    | Packet::PlayerAuthInput(_)
+                    | Packet::Unknown(_)
+            ),
+            SessionState::InGame | SessionState::Death => matches!(
+                packet,
+                Packet::RequestChunkRadius(_)
+                    | Packet::SetLocalPlayerAsInitialized(_)
+                    | Packet::PlayerAuthInput(_)
+                    | Packet::Text(_)
+                    | Packet::CommandRequest(_)
+                    | Packet::Unknown(_)
+            ),
+        }
+    }
+
+    /// Stages a session moves through, a state can only be left for the ones listed here.
+    pub fn can_transition_to(&self, next: SessionState) -> bool {
+        matches!(
+            (self, next),
+            (SessionState::Start, SessionState::Login)
+                | (SessionState::Login, SessionState::Encryption)
+                | (SessionState::Login, SessionState::ResourcePack)
+                | (SessionState::Encryption, SessionState::ResourcePack)
+                | (SessionState::ResourcePack, SessionState::PreSpawn)
+                | (SessionState::PreSpawn, SessionState::InGame)
+                | (SessionState::InGame, SessionState::Death)
+                | (SessionState::Death, SessionState::InGame)
+        )
+    }
+
+    /// How long a client may stay in this state, `None` once it is in game.
+    pub fn timeout(&self, properties: &ServerProperties) -> Option<Duration> {
+        let seconds = match self {
+            SessionState::Start | SessionState::Login | SessionState::Encryption => properties.login_timeout,
+            SessionState::ResourcePack => properties.resource_pack_timeout,
+            SessionState::PreSpawn => properties.spawn_timeout,
+            SessionState::InGame | SessionState::Death => 0,
+        };
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+}
+
 pub struct BedrockSession {
-    connection: Connection<ProtoHelperV785>,
-    state: SessionState
+    connection: SessionConnection,
+    state: SessionState,
+    context: ServerContext,
+    commands: mpsc::UnboundedReceiver<SessionCommand>,
+    command_sender: mpsc::UnboundedSender<SessionCommand>,
+    registered: bool,
+    login_data: Option<LoginData>,
+    entity_id: i64,
+    chunk_radius: i32,
+    state_entered: Instant,
+    unexpected_packets: u32,
 }
 
 impl BedrockSession {
-    pub fn new(connection: Connection<ProtoHelperV785>) -> BedrockSession {
-        BedrockSession { connection, state: SessionState::Start }
+    pub fn new(
+        connection: Connection<ProtoHelperV786>,
+        context: ServerContext,
+    ) -> BedrockSession {
+        let (command_sender, commands) = mpsc::unbounded_channel();
+        BedrockSession {
+            connection: SessionConnection::new(connection),
+            state: SessionState::Start,
+            context,
+            commands,
+            command_sender,
+            registered: false,
+            login_data: None,
+            entity_id: next_entity_id(),
+            chunk_radius: 0,
+            state_entered: Instant::now(),
+            unexpected_packets: 0,
+        }
+    }
+
+    pub fn get_resource_pack_manager(&self) -> Arc<ResourcePackManager> {
+        self.context.resource_pack_manager.clone()
+    }
+
+    pub fn get_level(&self) -> &Level {
+        &self.context.level
+    }
+
+    pub fn get_context(&self) -> &ServerContext {
+        &self.context
+    }
+
+    pub fn get_entity_id(&self) -> i64 {
+        self.entity_id
+    }
+
+    pub fn get_chunk_radius(&self) -> i32 {
+        self.chunk_radius
+    }
+
+    pub fn set_chunk_radius(&mut self, chunk_radius: i32) {
+        self.chunk_radius = chunk_radius;
+    }
+
+    pub fn get_login_data(&self) -> Option<&LoginData> {
+        self.login_data.as_ref()
+    }
+
+    pub fn set_login_data(&mut self, login_data: LoginData) {
+        self.login_data = Some(login_data);
+    }
+
+    pub fn get_protocol(&self) -> Option<ProtocolVersion> {
+        self.connection.version()
     }
 
-    pub fn change_state(&mut self, new_state: SessionState) {
-        println!("Transitioning from {:?} to {:?}", self.state, new_state);
+    pub fn switch_protocol(&mut self, version: ProtocolVersion) {
+        self.connection.switch_version(version);
+    }
+
+    pub async fn get_ip_address(&mut self) -> Option<SocketAddr> {
+        self.connection.get_ip_address().await.ok()
+    }
+
+    pub fn is_operator(&self) -> bool {
+        self.login_data.as_ref().is_some_and(|login_data| {
+            self.context.permissions.is_operator(&login_data.xuid, &login_data.display_name)
+        })
+    }
+
+    pub fn get_permission_level(&self) -> PermissionLevel {
+        match &self.login_data {
+            Some(login_data) => self.context.permissions.get_permission_level(&login_data.xuid, &login_data.display_name),
+            None => PermissionLevel::Any,
+        }
+    }
+
+    pub fn get_properties(&self) -> &ServerProperties {
+        &self.context.properties
+    }
+
+    /// Adds the logged in session to the server's registry, kicking an older session of
+    /// the same player.
+    pub async fn register(&mut self) -> Result<(), SessionError> {
+        let Some(login_data) = &self.login_data else {
+            return Err(SessionError::disconnect("disconnectionScreen.notAuthenticated"));
+        };
+        let handle = SessionHandle::new(
+            self.entity_id,
+            login_data.client_uuid,
+            login_data.xuid.clone(),
+            login_data.display_name.clone(),
+            self.get_ip_address().await,
+            self.command_sender.clone(),
+        );
+        if let Some(replaced) = self.context.sessions.register(handle) {
+            replaced.disconnect("disconnectionScreen.loggedinOtherLocation");
+        }
+        self.registered = true;
+        Ok(())
+    }
+
+    pub fn get_state(&self) -> SessionState {
+        self.state
+    }
+
+    /// Moves the session to `new_state`. Transitions that skip a stage are refused with
+    /// `SessionError::InvalidTransition`, which ends the session.
+    pub fn change_state(&mut self, new_state: SessionState) -> Result<(), SessionError> {
+        if !self.state.can_transition_to(new_state) {
+            return Err(SessionError::InvalidTransition(self.state, new_state));
+        }
+
+        debug!("Session {} moves from {:?} to {:?}", self.entity_id, self.state, new_state);
+        if new_state == SessionState::InGame && self.state == SessionState::PreSpawn {
+            self.context.advertisement.player_joined();
+            if let Some(handle) = self.context.sessions.get(self.entity_id) {
+                self.send_event(SessionEvent::Joined(handle));
+            }
+        }
         self.state = new_state;
+        self.state_entered = Instant::now();
+        Ok(())
     }
 
     pub async fn start(&mut self) {
+        match self.run().await {
+            Err(SessionError::Connection(err)) => info!("Connection closed: {:?}", err),
+            Err(err) => {
+                warn!("Closing session: {}", err);
+                match err.get_disconnect_message() {
+                    Some(message) => self.disconnect(message).await,
+                    None => self.close().await,
+                }
+            }
+            Ok(()) => self.close().await,
+        }
+        self.on_closed();
+    }
+
+    async fn run(&mut self) -> Result<(), SessionError> {
         loop {
-            let res = self.connection.recv().await;
-
-            if let Ok(packet) = res {
-                for (packet) in packet.iter() {
-                    match (self.state, packet) {
-                        (SessionState::Start, GamePackets::RequestNetworkSettings(packet_data)) => {
-                            session_start::handle(self, packet_data).await;
-                        }
-                        (SessionState::Login, GamePackets::Login(packet_data)) => {
-                            login_handler::handle(self, packet_data).await;
-                        }
-                        (SessionState::InGame, GamePackets::PlayerAuthInput(data)) => {
-                            // println!("PlayerAuthInput: {:?}", data);
-                        }
-                        (SessionState::Encryption, GamePackets::ClientToServerHandshake(packet_data)) => {
-                            println!("ClientToServerHandshake");
-                            client_to_server_handler::handle(self, packet_data).await;
-                        }
-                        (SessionState::ResourcePack, GamePackets::ResourcePackClientResponse(packet_data)) => {
-                            println!("packet_data {:?}", packet_data);
-                            resource_pack_handler::handle(self, packet_data).await;
-                        }
-                        (SessionState::ResourcePack, GamePackets::ResourcePackChunkRequest(packet_data)) => {
-                            resource_pack_chunk_request_handler::handle(self, packet_data).await;
-                        }
-                        (_, packet) => {
-                            println!("packet {:?} in state {:?}", packet, self.state);
-                        }
+            let deadline = self.state.timeout(&self.context.properties).map(|timeout| self.state_entered + timeout);
+            let packets = tokio::select! {
+                packets = self.connection.recv() => packets?,
+                Some(command) = self.commands.recv() => {
+                    self.handle_command(command).await?;
+                    continue
+                }
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    return Err(SessionError::Timeout(self.state));
+                }
+            };
+
+            for packet in packets.iter() {
+                if !self.state.allows(packet) {
+                    self.on_unexpected_packet(packet)?;
+                    continue
+                }
+
+                let received;
+                let packet = if self.context.event_bus.has_listeners::<PacketReceiveEvent>() {
+                    received = self.context.event_bus.fire(PacketReceiveEvent::new(self.entity_id, packet.clone()));
+                    if received.is_cancelled() {
+                        continue
+                    }
+                    &received.packet
+                } else {
+                    packet
+                };
+
+                match (self.state, packet) {
+                    (SessionState::Start, Packet::RequestNetworkSettings(packet_data)) => {
+                        session_start::handle(self, packet_data).await?;
+                    }
+                    (SessionState::Login, Packet::Login(packet_data)) => {
+                        login_handler::handle(self, packet_data).await?;
+                    }
+                    (SessionState::Encryption, Packet::ClientToServerHandshake(packet_data)) => {
+                        client_to_server_handler::handle(self, packet_data).await?;
+                    }
+                    (SessionState::ResourcePack, Packet::ResourcePackClientResponse(packet_data)) => {
+                        resource_pack_handler::handle(self, packet_data).await?;
+                    }
+                    (SessionState::ResourcePack, Packet::ResourcePackChunkRequest(packet_data)) => {
+                        resource_pack_chunk_request_handler::handle(self, packet_data).await?;
+                    }
+                    (SessionState::PreSpawn | SessionState::InGame, Packet::RequestChunkRadius(packet_data)) => {
+                        pre_spawn_handler::handle_chunk_radius(self, packet_data).await?;
+                    }
+                    (SessionState::PreSpawn, Packet::SetLocalPlayerAsInitialized(packet_data)) => {
+                        pre_spawn_handler::handle_initialized(self, packet_data).await?;
+                    }
+                    (SessionState::InGame | SessionState::Death, packet) => {
+                        self.send_event(SessionEvent::Packet(self.entity_id, packet.clone()));
                     }
+                    // Allowed but nothing to do yet, like input before the player spawned.
+                    _ => {}
                 }
-            } else {
-                println!("Connection closed: {:?}", res);
-                break
             }
         }
     }
 
+    /// Counts a packet that is not allowed in the current state, failing once the client
+    /// went over `max-unexpected-packets`.
+    fn on_unexpected_packet(&mut self, packet: &Packet) -> Result<(), SessionError> {
+        self.unexpected_packets += 1;
+        warn!("Unexpected {} packet in state {:?}", packet.get_name(), self.state);
+        debug!("Unexpected packet: {:?}", packet);
+
+        if self.unexpected_packets > self.context.properties.max_unexpected_packets {
+            return Err(SessionError::TooManyUnexpectedPackets(self.unexpected_packets));
+        }
+        Ok(())
+    }
+
+    async fn handle_command(&mut self, command: SessionCommand) -> Result<(), SessionError> {
+        match command {
+            SessionCommand::Send(packets) => self.send(&packets).await,
+            SessionCommand::Disconnect(message) => Err(SessionError::Disconnect(message)),
+        }
+    }
+
+    /// Hands an event to the main tick. Fails silently once the server stopped ticking.
+    fn send_event(&self, event: SessionEvent) {
+        let _ = self.context.events.send(event);
+    }
+
+    fn on_closed(&mut self) {
+        let in_game = matches!(self.state, SessionState::InGame | SessionState::Death);
+        if in_game {
+            self.context.advertisement.player_left();
+        }
+        if self.registered {
+            self.registered = false;
+            if let Some(handle) = self.context.sessions.unregister(self.entity_id) {
+                if in_game {
+                    self.send_event(SessionEvent::Left(handle));
+                }
+            }
+        }
+        self.state = SessionState::Start;
+    }
+
     pub async fn set_compression(
         &mut self,
         compression: Compression,
     ) {
 
-        self.connection.compression = Some(compression)
+        self.connection.set_compression(compression)
     }
 
     pub async fn set_encryption(
@@ -120,314 +374,47 @@ impl BedrockSession {
         encryption: Encryption,
     ) {
 
-        self.connection.encryption = Some(encryption)
+        self.connection.set_encryption(encryption)
     }
 
     pub async fn close(&mut self) {
         self.connection.close().await;
     }
 
-    pub async fn send(
-        &mut self,
-        gamepackets: &[<ProtoHelperV785 as ProtoHelper>::GamePacketType], // Use ProtoHelperV785 instead of T
-    ) {
-        self.connection.send(gamepackets).await.expect("Err send gamepackets");
-    }
-
-    pub async fn handle_login(&mut self) {
-        let time_start = Instant::now();
-
-        // NetworkSettingsRequest
-        // self.connection.recv().await.unwrap();
-        // println!("NetworkSettingsRequest");
-
-        let compression = Compression::Zlib {
-            threshold: 1,
-            compression_level: 7,
-        };
-
-        // NetworkSettings
-        // self.connection.send(&[GamePackets::NetworkSettings(NetworkSettingsPacket {
-        //     compression_threshold: 1,
-        //     compression_algorithm: PacketCompressionAlgorithm::ZLib,
-        //     client_throttle_enabled: false,
-        //     client_throttle_threshold: 0,
-        //     client_throttle_scalar: 0.0,
-        // })])
-        //     .await
-        //     .unwrap();
-        // println!("NetworkSettings");
-
-        self.connection.compression = Some(compression);
-
-        let login_data = self.connection.recv().await.unwrap();
-
-        // Login
-        println!("Login data {:?}", login_data);
-
-        self.connection
-            .send(&[
-                GamePackets::PlayStatus(PlayStatusPacket {
-                    status: PlayStatusType::LoginSuccess,
-                }),
-                GamePackets::ResourcePacksInfo(ResourcePacksInfoPacket {
-                    resource_pack_required: false,
-                    has_addon_packs: false,
-                    has_scripts: false,
-                    world_template_uuid: Default::default(),
-                    resource_packs: vec![],
-                    world_template_version: "".to_string(),
-                }),
-                GamePackets::ResourcePackStack(ResourcePackStackPacket {
-                    texture_pack_required: false,
-                    addon_list: vec![],
-                    base_game_version: BaseGameVersion(String::from("1.0")),
-                    experiments: Experiments {
-                        experiments: vec![],
-                        ever_toggled: false,
-                    },
-                    texture_pack_list: vec![],
-                    include_editor_packs: false,
-                }),
-            ])
-            .await
-            .unwrap();
-        println!("PlayStatus (LoginSuccess)");
-        println!("ResourcePacksInfo");
-        println!("ResourcePackStack");
-
-        // conn.send(&[
-        //     GamePackets::Disconnect(DisconnectPacket {
-        //         reason: ConnectionFailReason::Unknown,
-        //         messages: Some(DisconnectPacketMessage{
-        //             message: String::from("Test Disconnected"),
-        //             filtered_message: String::from("")
-        //         }),
-        //     })
-        // ])
-        // .await
-        // .unwrap();
-
-        let packet1 = StartGamePacket {
-            target_actor_id: ActorUniqueID(0),
-            target_runtime_id: ActorRuntimeID(0),
-            position: Vec3 {
-                x: 0.0,
-                y: 6.0,
-                z: 0.0,
-            },
-            rotation: Vec2 { x: 270.0, y: 90.0 },
-            settings: LevelSettings {
-                seed: 999,
-                spawn_settings: SpawnSettings {
-                    biome_type: SpawnBiomeType::Default,
-                    user_defined_biome_name: String::from("RandomBiome"),
-                    dimension: Dimension::Overworld,
-                },
-                generator_type: GeneratorType::Overworld,
-                game_type: GameType::Creative,
-                is_hardcore_mode_enabled: false,
-                game_difficulty: Difficulty::Peaceful,
-                achievements_disabled: true,
-                editor_world_type: EditorWorldType::NotEditor,
-                is_created_in_editor: false,
-                day_cycle_stop_time: -1,
-                education_edition_offer: EducationEditionOffer::None,
-                education_product_id: String::from(""),
-                rain_level: 0.0,
-                lightning_level: 0.0,
-                has_confirmed_platform_locked_content: false,
-                multiplayer_enabled: true,
-                lan_broadcasting_enabled: true,
-                xbox_live_broadcast_setting: GamePublishSetting::Public,
-                platform_broadcast_setting: GamePublishSetting::Public,
-                commands_enabled: false,
-                texture_packs_required: false,
-                experiments: Experiments {
-                    experiments: vec![],
-                    ever_toggled: false,
-                },
-                bonus_chest_enabled: false,
-                starting_map_enabled: false,
-                player_permissions: PlayerPermissionLevel::Member,
-                server_chunk_tick_range: 4,
-                locked_behaviour_pack: false,
-                from_locked_template: false,
-                from_template: false,
-                only_spawn_v1_villagers: false,
-                persona_disabled: false,
-                custom_skins_disabled: false,
-                emote_chat_muted: false,
-                base_game_version: BaseGameVersion(String::from("1.21.71")),
-                limited_world_width: 16,
-                limited_world_depth: 16,
-                edu_shared_uri_resource: EduSharedResourceUri {
-                    button_name: String::from(""),
-                    link_uri: String::from(""),
-                },
-                chat_restriction_level: ChatRestrictionLevel::None,
-                disable_player_interactions: false,
-                server_identifier: "".to_string(),
-                server_world_identifier: "".to_string(),
-                default_spawn_block_position: NetworkBlockPosition { x: 0, y: 0, z: 0 },
-                is_exported_from_editor: false,
-                education_features_enabled: false,
-                rule_data: GameRulesChangedPacketData { rules_list: vec![] },
-                locked_resource_pack: false,
-                use_msa_gamer_tags: false,
-                has_locked_template_settings: false,
-                nether_type: false,
-                override_force_experimental_gameplay: false,
-                server_scenario_identifier: "".to_string(),
-            },
-            level_id: String::from("PowerCrabX"),
-            level_name: String::from("Hello World"),
-            template_content_identity: String::from(""),
-            movement_settings: SyncedPlayerMovementSettings {
-                authority_mode: ServerAuthMovementMode::ServerAuthoritative,
-                rewind_history_size: 0,
-                server_authoritative_block_breaking: true,
-            },
-            current_level_time: 0,
-            enchantment_seed: 0,
-            block_properties: Vec::new(),
-            multiplayer_correlation_id: String::from(""),
-            server_version: String::from("1.21.71"),
-            player_property_data: nbtx::Value::Compound(HashMap::new()),
-            world_template_id: Uuid::nil(),
-            server_enabled_client_side_generation: false,
-            block_network_ids_are_hashes: true,
-            is_trial: false,
-            enable_item_stack_net_manager: false,
-            server_block_type_registry_checksum: 0,
-            network_permissions: NetworkPermissions {
-                server_auth_sound: false,
-            },
-            player_gamemode: Gamemode::Creative,
-        };
-
-        let mut playerlist = Vec::new();
-
-        for name in login_data.iter() {
-            match name {
-                GamePackets::Login(data) => {
-                    // playerlist.push(AddPlayerListEntry {
-                    //     uuid: data.client_uuid,
-                    //     target_actor_id: ActorUniqueID(0),
-                    //     player_name: data.clone().username,
-                    //     xbl_xuid: "".to_string(),
-                    //     platform_chat_id: "".to_string(),
-                    //     build_platform: BuildPlatform::Google,
-                    //     serialized_skin: data.clone().skin,
-                    //     is_teacher: false,
-                    //     is_host: false,
-                    //     is_sub_client: false,
-                    //     is_trusted_skin: true,
-                    // });
-                    println!("Login: {:?}", data.connection_request.is_xbox_auth());
-                }
-                _ => {}
-            }
+    /// Sends `Disconnect` with `message` and closes the connection. A failed send is only
+    /// logged since the connection is closed either way.
+    pub async fn disconnect(&mut self, message: &str) {
+        let result = self.send(&[Packet::Disconnect(DisconnectPacket {
+            reason: ConnectionFailReason::Unknown,
+            messages: Some(DisconnectPacketMessage {
+                message: message.to_string(),
+                filtered_message: String::new(),
+            }),
+        })])
+            .await;
+        if let Err(err) = result {
+            warn!("Failed to send disconnect: {}", err);
         }
+        self.close().await;
+    }
 
-        let mut att: Vec<AttributeData> = Vec::new();
-        att.push(AttributeData {
-            min_value: 0.0,
-            max_value: 340282346638528859811704183484516925440.00,
-            current_value: 0.1,
-            default_min_value: 0.0,
-            default_max_value: 340282346638528859811704183484516925440.00,
-            default_value: 0.1,
-            name: String::from("minecraft:movement"),
-            modifiers: Vec::new(),
-        });
-
-        self.connection
-            .send(&[
-                GamePackets::StartGame(packet1),
-                GamePackets::ItemRegistry(ItemRegistryPacket { items: vec![] }),
-                GamePackets::PlayerList(PlayerListPacket {
-                    action: PlayerListPacketType::Add {
-                        add_player_list: playerlist,
-                    },
-                }),
-                GamePackets::UpdateAttributes(UpdateAttributesPacket {
-                    target_runtime_id: ActorRuntimeID(0),
-                    attribute_list: att,
-                    tick: 0,
-                }),
-            ])
-            .await
-            .unwrap();
-        println!("StartGame");
-
-        let chunk_position_x = 0 >> 4;
-        let chunk_position_z = 0 >> 4;
-        let chunk_radius = 30;
-        for x in -chunk_radius..chunk_radius {
-            for z in -chunk_radius..chunk_radius {
-                let mut chunk = LevelChunkPacket {
-                    chunk_position: ChunkPos {
-                        x: 0 >> 4,
-                        z: 0 >> 4,
-                    },
-                    dimension_id: 0,
-                    cache_enabled: false,
-                    cache_blobs: vec![],
-                    serialized_chunk_data: "".to_string(),
-                };
-                chunk.chunk_position.x = chunk_position_x + x;
-                chunk.chunk_position.z = chunk_position_z + z;
-                self.connection
-                    .send(&[GamePackets::LevelChunk(chunk)])
-                    .await
-                    .unwrap();
-            }
+    pub async fn send(
+        &mut self,
+        gamepackets: &[Packet],
+    ) -> Result<(), SessionError> {
+        if !self.context.event_bus.has_listeners::<PacketSendEvent>() {
+            self.connection.send(gamepackets).await?;
+            return Ok(());
         }
 
-        self.connection
-            .send(&[GamePackets::PlayStatus(PlayStatusPacket {
-                status: PlayStatusType::PlayerSpawn,
-            })])
-            .await
-            .unwrap();
-        println!("PlayStatusPacket (PlayerSpawn)");
-
-        let time_end = Instant::now();
-
-        println!("{:?}", time_end.duration_since(time_start));
-
-        loop {
-            let res = self.connection.recv().await;
-
-            if let Ok(packet) = res {
-                for (packet) in packet.iter() {
-                    match packet {
-                        GamePackets::PlayerAuthInput(data) => {
-                            // println!("PlayerAuthInput: {:?}", data);
-                        }
-                        _ => {
-                            println!("packet: {:?}", packet);
-                        }
-                    }
-                }
-            } else {
-                match res {
-                    Ok(_) => {}
-                    Err(err) => match err {
-                        TransportError(err) => match err {
-                            TransportLayerError::RakNetError(_) => {
-                                println!("close connection raknet closed");
-                                break;
-                            }
-                            _ => break,
-                        },
-                        ConnectionError::ProtoCodecError(_)
-                        | ConnectionError::ConnectionClosed
-                        | ConnectionError::IOError(_) => break,
-                    },
-                }
-            }
+        let gamepackets: Vec<Packet> = gamepackets.iter()
+            .map(|packet| self.context.event_bus.fire(PacketSendEvent::new(self.entity_id, packet.clone())))
+            .filter(|event| !event.is_cancelled())
+            .map(|event| event.packet)
+            .collect();
+        if !gamepackets.is_empty() {
+            self.connection.send(&gamepackets).await?;
         }
+        Ok(())
     }
 }
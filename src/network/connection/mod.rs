@@ -1 +1,3 @@
-pub mod bedrock_session;
\ No newline at end of file
+pub mod bedrock_session;
+pub mod session_connection;
+pub mod session_error;
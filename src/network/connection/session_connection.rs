@@ -0,0 +1,107 @@
+use std::net::SocketAddr;
+use bedrockrs::proto::compression::Compression;
+use bedrockrs::proto::connection::Connection;
+use bedrockrs::proto::encryption::Encryption;
+use bedrockrs::proto::error::ConnectionError;
+use bedrockrs::proto::v776::helper::ProtoHelperV776;
+use bedrockrs::proto::v785::helper::ProtoHelperV785;
+use bedrockrs::proto::v786::helper::ProtoHelperV786;
+use log::warn;
+use crate::network::protocol::packet::Packet;
+use crate::network::protocol::{v776, v785, v786, ProtocolVersion};
+
+/// A client connection bound to the bedrockrs `ProtoHelper` of the client's protocol.
+/// Connections are accepted as the latest version, since `RequestNetworkSettings` is
+/// identical across versions, and switched once the client announced its protocol.
+pub enum SessionConnection {
+    V776(Connection<ProtoHelperV776>),
+    V785(Connection<ProtoHelperV785>),
+    V786(Connection<ProtoHelperV786>),
+    Closed,
+}
+
+macro_rules! with_connection {
+    ($self:expr, $conn:ident => $body:expr, $closed:expr) => {
+        match $self {
+            SessionConnection::V776($conn) => $body,
+            SessionConnection::V785($conn) => $body,
+            SessionConnection::V786($conn) => $body,
+            SessionConnection::Closed => $closed,
+        }
+    };
+}
+
+impl SessionConnection {
+    pub fn new(connection: Connection<ProtoHelperV786>) -> SessionConnection {
+        SessionConnection::V786(connection)
+    }
+
+    pub fn version(&self) -> Option<ProtocolVersion> {
+        match self {
+            SessionConnection::V776(_) => Some(ProtocolVersion::V776),
+            SessionConnection::V785(_) => Some(ProtocolVersion::V785),
+            SessionConnection::V786(_) => Some(ProtocolVersion::V786),
+            SessionConnection::Closed => None,
+        }
+    }
+
+    pub fn switch_version(&mut self, version: ProtocolVersion) {
+        if self.version() == Some(version) {
+            return;
+        }
+        let connection = std::mem::replace(self, SessionConnection::Closed);
+        *self = with_connection!(connection, conn => match version {
+            ProtocolVersion::V776 => SessionConnection::V776(conn.into_helper()),
+            ProtocolVersion::V785 => SessionConnection::V785(conn.into_helper()),
+            ProtocolVersion::V786 => SessionConnection::V786(conn.into_helper()),
+        }, SessionConnection::Closed);
+    }
+
+    pub async fn recv(&mut self) -> Result<Vec<Packet>, ConnectionError> {
+        match self {
+            SessionConnection::V776(conn) => Ok(conn.recv().await?.into_iter().map(v776::decode).collect()),
+            SessionConnection::V785(conn) => Ok(conn.recv().await?.into_iter().map(v785::decode).collect()),
+            SessionConnection::V786(conn) => Ok(conn.recv().await?.into_iter().map(v786::decode).collect()),
+            SessionConnection::Closed => Err(ConnectionError::ConnectionClosed),
+        }
+    }
+
+    pub async fn send(&mut self, packets: &[Packet]) -> Result<(), ConnectionError> {
+        match self {
+            SessionConnection::V776(conn) => conn.send(&encode_all(packets, ProtocolVersion::V776, v776::encode)).await,
+            SessionConnection::V785(conn) => conn.send(&encode_all(packets, ProtocolVersion::V785, v785::encode)).await,
+            SessionConnection::V786(conn) => conn.send(&encode_all(packets, ProtocolVersion::V786, v786::encode)).await,
+            SessionConnection::Closed => Err(ConnectionError::ConnectionClosed),
+        }
+    }
+
+    pub fn set_compression(&mut self, compression: Compression) {
+        with_connection!(self, conn => conn.compression = Some(compression), ())
+    }
+
+    pub fn set_encryption(&mut self, encryption: Encryption) {
+        with_connection!(self, conn => conn.encryption = Some(encryption), ())
+    }
+
+    pub async fn get_ip_address(&mut self) -> Result<SocketAddr, ConnectionError> {
+        with_connection!(self, conn => conn.get_ip_address().await, Err(ConnectionError::ConnectionClosed))
+    }
+
+    pub async fn close(&mut self) {
+        with_connection!(self, conn => { conn.close().await; }, ())
+    }
+}
+
+/// Encodes `packets` for `version`. Packets the version has no mapping for are left out
+/// and reported, since a client missing one may otherwise hang without a trace.
+fn encode_all<P>(packets: &[Packet], version: ProtocolVersion, encode: fn(Packet) -> Option<P>) -> Vec<P> {
+    packets.iter()
+        .filter_map(|packet| {
+            let encoded = encode(packet.clone());
+            if encoded.is_none() {
+                warn!("Dropping {} packet, protocol {} cannot encode it", packet.get_name(), version.protocol());
+            }
+            encoded
+        })
+        .collect()
+}
@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::fmt;
+use bedrockrs::proto::error::ConnectionError;
+use crate::network::connection::bedrock_session::SessionState;
+
+/// Why a session ended. Handlers return it instead of panicking, `BedrockSession::start`
+/// logs it and closes the connection, telling the client why where it still can.
+#[derive(Debug)]
+pub enum SessionError {
+    /// The transport failed or the client went away.
+    Connection(ConnectionError),
+    /// The client is kicked with the message shown on its disconnect screen.
+    Disconnect(String),
+    InvalidTransition(SessionState, SessionState),
+    Timeout(SessionState),
+    TooManyUnexpectedPackets(u32),
+}
+
+impl SessionError {
+    pub fn disconnect(message: &str) -> SessionError {
+        SessionError::Disconnect(message.to_string())
+    }
+
+    /// Message sent in the `Disconnect` packet, `None` when the connection is already unusable.
+    pub fn get_disconnect_message(&self) -> Option<&str> {
+        match self {
+            SessionError::Connection(_) => None,
+            SessionError::Disconnect(message) => Some(message),
+            SessionError::InvalidTransition(_, _) => Some("disconnectionScreen.noReason"),
+            SessionError::Timeout(_) => Some("disconnectionScreen.timeout"),
+            SessionError::TooManyUnexpectedPackets(_) => Some("disconnectionScreen.badPacket"),
+        }
+    }
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Connection(err) => write!(f, "connection error: {:?}", err),
+            SessionError::Disconnect(message) => write!(f, "disconnected: {}", message),
+            SessionError::InvalidTransition(from, to) => write!(f, "invalid state transition from {:?} to {:?}", from, to),
+            SessionError::Timeout(state) => write!(f, "timed out in state {:?}", state),
+            SessionError::TooManyUnexpectedPackets(count) => write!(f, "{} unexpected packets", count),
+        }
+    }
+}
+
+impl Error for SessionError {}
+
+impl From<ConnectionError> for SessionError {
+    fn from(err: ConnectionError) -> Self {
+        SessionError::Connection(err)
+    }
+}
@@ -0,0 +1,271 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::Utc;
+use p384::ecdsa::signature::Verifier;
+use p384::ecdsa::{Signature, VerifyingKey};
+use p384::pkcs8::DecodePublicKey;
+use serde_json::Value;
+use uuid::Uuid;
+
+pub static MOJANG_ROOT_PUBLIC_KEY: &str = "MHYwEAYHKoZIzj0CAQYFK4EEACIDYgAECRXueJeTDqNRRgJi/vlRufByu/2G0i2Ebt6YMar5QX/R0DIIyrJMcUpruK4QveTfJSTp3Shlq4Gk34cD/4GUWwkv0DVuzeuB+tXija7HBxii03NHDbPAD0AKnLr2wdAp";
+
+// Allowed clock drift between the client, Xbox Live and this server.
+const CLOCK_DRIFT_SECONDS: i64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct LoginData {
+    pub xuid: String,
+    pub display_name: String,
+    pub client_uuid: Uuid,
+    pub identity_public_key: String,
+    pub xbox_authenticated: bool,
+    pub client_data: Value,
+}
+
+impl LoginData {
+    /// Verifies the login certificate chain and the client data token signed by the
+    /// chain's identity key. With `xbox_auth` the chain must be rooted at Mojang's key.
+    ///
+    /// The identity is only read from the last link, and a link signed by Mojang must be
+    /// the one right before it, so nothing can be appended after the link Xbox Live
+    /// issued. Chains without Mojang's signature get no XUID.
+    pub fn from_chain(chain: &[String], client_data_jwt: &str, xbox_auth: bool) -> Result<LoginData, String> {
+        LoginData::verify(chain, client_data_jwt, xbox_auth, MOJANG_ROOT_PUBLIC_KEY)
+    }
+
+    fn verify(chain: &[String], client_data_jwt: &str, xbox_auth: bool, root_key: &str) -> Result<LoginData, String> {
+        if chain.is_empty() || chain.len() > 3 {
+            return Err(format!("Invalid chain length {}", chain.len()));
+        }
+
+        let now = Utc::now().timestamp();
+        let mut current_key: Option<String> = None;
+        let mut xbox_authenticated = false;
+        let mut extra_data: Option<Value> = None;
+
+        for (index, token) in chain.iter().enumerate() {
+            let is_last = index + 1 == chain.len();
+            let (header, claims) = decode_jwt(token)?;
+            let x5u = header.get("x5u").and_then(Value::as_str).ok_or("Missing x5u in chain header")?;
+
+            match &current_key {
+                Some(key) if key != x5u => return Err("Chain link signed by an unexpected key".to_string()),
+                _ => {}
+            }
+            verify_jwt(token, x5u)?;
+
+            if x5u == root_key {
+                if index + 2 != chain.len() {
+                    return Err("Xbox Live signed link must be followed by exactly one link".to_string());
+                }
+                xbox_authenticated = true;
+            }
+            check_timestamps(&claims, now)?;
+
+            if let Some(data) = claims.get("extraData") {
+                if extra_data.is_some() {
+                    return Err("Duplicate extraData in chain".to_string());
+                }
+                if !is_last {
+                    return Err("extraData outside the last chain link".to_string());
+                }
+                extra_data = Some(data.clone());
+            }
+            current_key = Some(
+                claims.get("identityPublicKey")
+                    .and_then(Value::as_str)
+                    .ok_or("Missing identityPublicKey in chain claims")?
+                    .to_string(),
+            );
+        }
+
+        if xbox_auth && !xbox_authenticated {
+            return Err("Chain is not signed by Xbox Live".to_string());
+        }
+
+        let identity_public_key = current_key.ok_or("Missing identity public key")?;
+        verify_jwt(client_data_jwt, &identity_public_key)?;
+        let (_, client_data) = decode_jwt(client_data_jwt)?;
+
+        let extra_data = extra_data.ok_or("Missing extraData in chain")?;
+        let display_name = extra_data.get("displayName")
+            .and_then(Value::as_str)
+            .ok_or("Missing displayName in chain")?
+            .to_string();
+        let client_uuid = extra_data.get("identity")
+            .and_then(Value::as_str)
+            .and_then(|uuid| Uuid::parse_str(uuid).ok())
+            .ok_or("Missing or invalid identity in chain")?;
+        // Anyone can sign a chain of their own, only Xbox Live vouches for the XUID.
+        let xuid = if xbox_authenticated {
+            extra_data.get("XUID").and_then(Value::as_str).unwrap_or_default().to_string()
+        } else {
+            String::new()
+        };
+
+        if xbox_authenticated && xuid.is_empty() {
+            return Err("Missing XUID in authenticated chain".to_string());
+        }
+
+        Ok(LoginData {
+            xuid,
+            display_name,
+            client_uuid,
+            identity_public_key,
+            xbox_authenticated,
+            client_data,
+        })
+    }
+}
+
+fn decode_jwt(token: &str) -> Result<(Value, Value), String> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(claims), Some(_), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err("Malformed JWT".to_string());
+    };
+    Ok((decode_json(header)?, decode_json(claims)?))
+}
+
+fn decode_json(part: &str) -> Result<Value, String> {
+    let bytes = URL_SAFE_NO_PAD.decode(part.trim_end_matches('='))
+        .map_err(|e| format!("Invalid JWT encoding: {}", e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Invalid JWT json: {}", e))
+}
+
+fn verify_jwt(token: &str, public_key: &str) -> Result<(), String> {
+    let (signing_input, signature) = token.rsplit_once('.').ok_or("Malformed JWT")?;
+
+    let der = STANDARD.decode(public_key).map_err(|e| format!("Invalid public key encoding: {}", e))?;
+    let key = VerifyingKey::from_public_key_der(&der).map_err(|e| format!("Invalid public key: {}", e))?;
+
+    let signature = URL_SAFE_NO_PAD.decode(signature.trim_end_matches('='))
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+    let signature = Signature::from_slice(&signature).map_err(|e| format!("Invalid signature: {}", e))?;
+
+    key.verify(signing_input.as_bytes(), &signature)
+        .map_err(|_| "Invalid JWT signature".to_string())
+}
+
+fn check_timestamps(claims: &Value, now: i64) -> Result<(), String> {
+    if let Some(nbf) = claims.get("nbf").and_then(Value::as_i64) {
+        if nbf > now + CLOCK_DRIFT_SECONDS {
+            return Err("Chain link is not valid yet".to_string());
+        }
+    }
+    if let Some(exp) = claims.get("exp").and_then(Value::as_i64) {
+        if exp < now - CLOCK_DRIFT_SECONDS {
+            return Err("Chain link has expired".to_string());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p384::ecdsa::SigningKey;
+    use p384::ecdsa::signature::Signer;
+    use p384::pkcs8::EncodePublicKey;
+    use rand_core::OsRng;
+    use serde_json::json;
+
+    const IDENTITY: &str = "8f6f3b3e-2a4c-4d8e-9b1a-0c6d5e4f3a2b";
+
+    /// Stands in for Mojang's root, Xbox Live's intermediate and the client's key.
+    struct Keys {
+        root: SigningKey,
+        intermediate: SigningKey,
+        client: SigningKey,
+    }
+
+    impl Keys {
+        fn generate() -> Keys {
+            Keys {
+                root: SigningKey::random(&mut OsRng),
+                intermediate: SigningKey::random(&mut OsRng),
+                client: SigningKey::random(&mut OsRng),
+            }
+        }
+
+        /// The three links a client sends after signing in to Xbox Live.
+        fn xbox_chain(&self, xuid: &str) -> Vec<String> {
+            vec![
+                sign(&self.client, json!({ "identityPublicKey": public_key(&self.root) })),
+                sign(&self.root, json!({ "identityPublicKey": public_key(&self.intermediate) })),
+                sign(&self.intermediate, identity_claims(xuid, "Steve", &self.client)),
+            ]
+        }
+
+        fn client_data(&self) -> String {
+            sign(&self.client, json!({ "ServerAddress": "127.0.0.1:19132" }))
+        }
+
+        fn verify(&self, chain: &[String], xbox_auth: bool) -> Result<LoginData, String> {
+            LoginData::verify(chain, &self.client_data(), xbox_auth, &public_key(&self.root))
+        }
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        STANDARD.encode(key.verifying_key().to_public_key_der().unwrap().as_bytes())
+    }
+
+    fn sign(key: &SigningKey, claims: Value) -> String {
+        let header = json!({ "alg": "ES384", "x5u": public_key(key) });
+        let input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string()),
+        );
+        let signature: Signature = key.sign(input.as_bytes());
+        format!("{}.{}", input, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+
+    fn identity_claims(xuid: &str, name: &str, identity_key: &SigningKey) -> Value {
+        json!({
+            "extraData": { "XUID": xuid, "displayName": name, "identity": IDENTITY },
+            "identityPublicKey": public_key(identity_key),
+        })
+    }
+
+    #[test]
+    fn accepts_xbox_chain() {
+        let keys = Keys::generate();
+        let login = keys.verify(&keys.xbox_chain("2535400000000001"), true).unwrap();
+        assert!(login.xbox_authenticated);
+        assert_eq!(login.xuid, "2535400000000001");
+        assert_eq!(login.display_name, "Steve");
+    }
+
+    #[test]
+    fn rejects_link_appended_after_xbox_link() {
+        let keys = Keys::generate();
+        let mut chain = keys.xbox_chain("2535400000000001");
+        chain.remove(0);
+        chain.push(sign(&keys.client, identity_claims("2535400000000002", "Owner", &keys.client)));
+        assert!(keys.verify(&chain, true).is_err());
+        assert!(keys.verify(&chain, false).is_err());
+    }
+
+    #[test]
+    fn rejects_duplicate_extra_data() {
+        let keys = Keys::generate();
+        let mut chain = keys.xbox_chain("2535400000000001");
+        chain[0] = sign(&keys.client, json!({
+            "extraData": { "XUID": "2535400000000002", "displayName": "Owner", "identity": IDENTITY },
+            "identityPublicKey": public_key(&keys.root),
+        }));
+        assert!(keys.verify(&chain, true).is_err());
+    }
+
+    #[test]
+    fn self_signed_chain_gets_no_xuid() {
+        let keys = Keys::generate();
+        let chain = vec![sign(&keys.client, identity_claims("2535400000000002", "Owner", &keys.client))];
+        assert!(keys.verify(&chain, true).is_err());
+
+        let login = keys.verify(&chain, false).unwrap();
+        assert!(!login.xbox_authenticated);
+        assert!(login.xuid.is_empty());
+        assert_eq!(login.display_name, "Owner");
+    }
+}
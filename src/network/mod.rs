@@ -1,46 +1,85 @@
+mod acceptor;
+pub mod advertisement;
 mod connection;
+pub mod encryption;
+pub mod login_data;
+pub mod pack_cdn;
 mod process;
+pub mod protocol;
 mod protocol_info;
+pub mod session_registry;
 
-use bedrockrs::proto::connection::Connection;
+use std::net::SocketAddr;
 use bedrockrs::proto::listener::Listener;
-use bedrockrs::proto::v785::helper::ProtoHelperV785;
-use crate::network::connection::bedrock_session::BedrockSession;
-use crate::server::Server;
+use log::{error, info, warn};
+use crate::network::protocol_info::{CURRENT_PROTOCOL, GAME_VERSION};
+use crate::server::{Server, ServerContext};
 
 pub struct Network {
     pub name: String,
 }
 
 impl Network {
-    pub async fn new(server: tokio::sync::MutexGuard<'_, Server>) -> Network {
+    pub async fn new(server: &Server) -> Network {
+        let context = server.get_context();
+        let properties = context.properties.clone();
+
+        match properties.ipv4_address() {
+            Ok(address) => Network::listen(address, context.clone()),
+            Err(err) => error!("{}", err),
+        }
+        match properties.ipv6_address() {
+            Ok(address) => Network::listen(address, context.clone()),
+            Err(err) => error!("{}", err),
+        }
+
+        if properties.pack_cdn {
+            if properties.is_pack_cdn_unreachable() {
+                warn!("pack-cdn-url is not set and server-ip is {}, clients will download packs in chunks", properties.server_ip);
+            }
+            match properties.pack_cdn_address() {
+                Ok(address) => {
+                    if let Err(err) = pack_cdn::start(address, context.resource_pack_manager.clone()).await {
+                        error!("Failed to bind pack CDN on {}: {}", address, err);
+                    }
+                }
+                Err(err) => error!("{}", err),
+            }
+        }
+
+        Network {
+            name: properties.motd.clone(),
+        }
+    }
+
+    fn listen(address: SocketAddr, context: ServerContext) {
         tokio::spawn(async move {
-            let mut listener = Listener::new_raknet(
-                "PowerCrabX!!!".to_string(),
-                "Hello World".to_string(),
-                "1.0".to_string(),
-                20,
-                10,
-                "127.0.0.1:19132".parse().unwrap(),
+            let pong_data = context.advertisement.subscribe().borrow().clone();
+            let mut listener = match Listener::new_raknet(
+                pong_data.motd.clone(),
+                pong_data.sub_motd.clone(),
+                GAME_VERSION.to_string(),
+                pong_data.max_players,
+                pong_data.player_count,
+                address,
                 false,
             )
-                .await
-                .unwrap();
-
-            listener.start().await.unwrap();
-            loop {
-                let mut conn: Connection<ProtoHelperV785> = listener.accept().await.unwrap();
-                println!("{}", conn.get_ip_address().await.unwrap());
-                tokio::spawn(async move {
-                    println!("spawn Task");
-                    let mut bedrock_session = BedrockSession::new(conn);
-                    bedrock_session.start().await;
-                });
+                .await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    error!("Failed to bind {}: {:?}", address, err);
+                    return;
+                }
+            };
+            listener.set_protocol(CURRENT_PROTOCOL as u16);
+
+            if let Err(err) = listener.start().await {
+                error!("Failed to start listener on {}: {:?}", address, err);
+                return;
             }
-        });
+            info!("Listening on {}", address);
 
-        Network {
-            name: String::new(),
-        }
+            acceptor::run(listener, context, address).await;
+        });
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,187 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use crate::resource_pack::resource_pack_manager::ResourcePackManager;
+
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+/// Time a client gets to send its request head after connecting.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a whole request may take, including sending the pack.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub static PACK_PATH_PREFIX: &str = "/packs/";
+
+/// Minimal HTTP/1.1 file server for the packs held by the `ResourcePackManager`.
+/// Packs are served as `GET /packs/<uuid>_<version>.zip`, clients that cannot reach it
+/// fall back to the in-band chunk requests. Returns the bound address.
+pub async fn start(address: SocketAddr, manager: Arc<ResourcePackManager>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address).await?;
+    let address = listener.local_addr()?;
+    info!("Pack CDN listening on http://{}", address);
+
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    let manager = manager.clone();
+                    tokio::spawn(async move {
+                        match timeout(CONNECTION_TIMEOUT, handle_connection(stream, &manager)).await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(err)) => warn!("Pack CDN request from {} failed: {}", peer, err),
+                            Err(_) => warn!("Pack CDN request from {} timed out", peer),
+                        }
+                    });
+                }
+                Err(err) => {
+                    warn!("Pack CDN accept failed: {}", err);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    });
+    Ok(address)
+}
+
+async fn handle_connection(mut stream: TcpStream, manager: &ResourcePackManager) -> io::Result<()> {
+    let head = match timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(Ok(Some(head))) => head,
+        Ok(Ok(None)) => return Ok(()),
+        Ok(Err(err)) if err.kind() == io::ErrorKind::InvalidData => {
+            return write_status(&mut stream, "431 Request Header Fields Too Large").await;
+        }
+        Ok(Err(err)) => return Err(err),
+        Err(_) => return write_status(&mut stream, "408 Request Timeout").await,
+    };
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (request_line.next().unwrap_or_default(), request_line.next().unwrap_or_default());
+
+    if method != "GET" && method != "HEAD" {
+        return write_status(&mut stream, "405 Method Not Allowed").await;
+    }
+
+    let pack = path.strip_prefix(PACK_PATH_PREFIX)
+        .and_then(|file| file.strip_suffix(".zip"))
+        .and_then(|pack_id| manager.get_pack_by_id(pack_id));
+    let Some(pack) = pack else {
+        return write_status(&mut stream, "404 Not Found").await;
+    };
+
+    let data = pack.get_data();
+    let header = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/zip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        data.len(),
+    );
+    stream.write_all(header.as_bytes()).await?;
+    if method == "GET" {
+        stream.write_all(data).await?;
+    }
+    stream.shutdown().await
+}
+
+/// Reads up to the end of the request head, `None` when the client closed the
+/// connection first.
+async fn read_head(stream: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+    let mut head = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        head.extend_from_slice(&buffer[..read]);
+        if head.len() > MAX_REQUEST_HEAD {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "request head too large"));
+        }
+    }
+    Ok(Some(head))
+}
+
+async fn write_status(stream: &mut TcpStream, status: &str) -> io::Result<()> {
+    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+    use sha2::{Digest, Sha256};
+    use crate::resource_pack::pack::tests::{pack_bytes, PACK_UUID};
+
+    fn load_manager(name: &str) -> (PathBuf, Arc<ResourcePackManager>) {
+        let root = std::env::temp_dir().join(format!("powercrabx-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("resource_packs")).unwrap();
+        fs::write(root.join("resource_packs/test.mcpack"), pack_bytes(50_000)).unwrap();
+        let manager = ResourcePackManager::load(
+            &root.join("resource_packs"),
+            &root.join("behavior_packs"),
+            HashMap::new(),
+            1024,
+        );
+        (root, Arc::new(manager))
+    }
+
+    async fn request(address: SocketAddr, request: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+
+        let split = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..split]).to_string();
+        (head, response[split + 4..].to_vec())
+    }
+
+    async fn get(address: SocketAddr, path: &str) -> (String, Vec<u8>) {
+        request(address, &format!("GET {} HTTP/1.1\r\nHost: 127.0.0.1\r\n\r\n", path)).await
+    }
+
+    #[tokio::test]
+    async fn serves_pack_bytes() {
+        let (root, manager) = load_manager("cdn-serve");
+        let pack = manager.get_pack_by_id(PACK_UUID).unwrap();
+        let address = start("127.0.0.1:0".parse().unwrap(), manager.clone()).await.unwrap();
+
+        let (head, body) = get(address, &format!("{}{}.zip", PACK_PATH_PREFIX, pack.get_pack_id())).await;
+        assert!(head.starts_with("HTTP/1.1 200 OK"), "{}", head);
+        assert!(head.contains(&format!("Content-Length: {}", pack.get_pack_size())));
+        assert_eq!(body, pack.get_data());
+        assert_eq!(Sha256::digest(&body).to_vec(), pack.sha256);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_packs_and_traversal() {
+        let (root, manager) = load_manager("cdn-reject");
+        let address = start("127.0.0.1:0".parse().unwrap(), manager).await.unwrap();
+
+        for path in [
+            "/packs/00000000-0000-0000-0000-000000000000_1.0.0.zip",
+            "/packs/../server.properties",
+            "/packs/../../resource_packs/test.mcpack.zip",
+            "/packs/%2e%2e/server.properties.zip",
+            "/server.properties",
+        ] {
+            let (head, body) = get(address, path).await;
+            assert!(head.starts_with("HTTP/1.1 404"), "{}: {}", path, head);
+            assert!(body.is_empty());
+        }
+
+        let (head, _) = request(address, "POST /packs/x.zip HTTP/1.1\r\n\r\n").await;
+        assert!(head.starts_with("HTTP/1.1 405"), "{}", head);
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -3,4 +3,5 @@ pub mod session_start;
 pub mod login_handler;
 pub mod client_to_server_handler;
 pub mod resource_pack_handler;
-pub mod resource_pack_chunk_request_handler;
\ No newline at end of file
+pub mod resource_pack_chunk_request_handler;
+pub mod pre_spawn_handler;
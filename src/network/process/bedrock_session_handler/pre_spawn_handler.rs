@@ -0,0 +1,213 @@
+use crate::network::connection::bedrock_session::{BedrockSession, SessionState};
+use crate::network::connection::session_error::SessionError;
+use crate::network::protocol::packet::Packet;
+use crate::network::protocol_info::GAME_VERSION;
+use crate::permission::abilities;
+use bedrockrs::proto::v662::enums::{
+    Dimension, EditorWorldType, EducationEditionOffer, GamePublishSetting, GeneratorType,
+    ServerAuthMovementMode,
+};
+use bedrockrs::proto::v662::packets::{
+    ChunkRadiusUpdatedPacket, LevelChunkPacket, RequestChunkRadiusPacket, SetLocalPlayerAsInitializedPacket,
+};
+use bedrockrs::proto::v662::types::{
+    ActorRuntimeID, ActorUniqueID, GameRulesChangedPacketData, NetworkBlockPosition, SyncedPlayerMovementSettings,
+};
+use bedrockrs::proto::v729::packets::play_status::PlayStatusPacket;
+use bedrockrs::proto::v729::types::base_game_version::BaseGameVersion;
+use bedrockrs::proto::v729::types::chat_restriction_level::ChatRestrictionLevel;
+use bedrockrs::proto::v729::types::chunk_pos::ChunkPos;
+use bedrockrs::proto::v729::types::edu_shared_uri_resource::EduSharedResourceUri;
+use bedrockrs::proto::v729::types::network_permissions::NetworkPermissions;
+use bedrockrs::proto::v729::types::play_status::PlayStatusType;
+use bedrockrs::proto::v729::types::spawn_biome_type::SpawnBiomeType;
+use bedrockrs::proto::v729::types::spawn_settings::SpawnSettings;
+use bedrockrs::proto::v748::packets::{AttributeData, UpdateAttributesPacket};
+use bedrockrs::proto::v748::types::LevelSettings;
+use bedrockrs::proto::v776::packets::{ItemRegistryPacket, StartGamePacket};
+use std::collections::HashMap;
+use uuid::Uuid;
+use vek::{Vec2, Vec3};
+
+/// Sends the world to a client that finished the resource pack exchange. Chunks follow
+/// once the client asked for its chunk radius.
+pub async fn start(session: &mut BedrockSession) -> Result<(), SessionError> {
+    let start_game = build_start_game(session);
+
+    let attributes = vec![AttributeData {
+        min_value: 0.0,
+        max_value: f32::MAX,
+        current_value: 0.1,
+        default_min_value: 0.0,
+        default_max_value: f32::MAX,
+        default_value: 0.1,
+        name: String::from("minecraft:movement"),
+        modifiers: Vec::new(),
+    }];
+
+    session.send(&[
+        Packet::StartGame(start_game),
+        Packet::ItemRegistry(ItemRegistryPacket { items: vec![] }),
+        Packet::UpdateAttributes(UpdateAttributesPacket {
+            target_runtime_id: ActorRuntimeID(session.get_entity_id() as u64),
+            attribute_list: attributes,
+            tick: 0,
+        }),
+        abilities::build(
+            session.get_entity_id(),
+            session.get_permission_level(),
+            session.is_operator(),
+            session.get_properties().get_game_type(),
+        ),
+    ]).await
+}
+
+pub async fn handle_chunk_radius(session: &mut BedrockSession, packet_data: &RequestChunkRadiusPacket) -> Result<(), SessionError> {
+    let first_request = session.get_chunk_radius() == 0;
+    let chunk_radius = packet_data.chunk_radius.min(session.get_properties().view_distance).max(1);
+    session.set_chunk_radius(chunk_radius);
+
+    session.send(&[Packet::ChunkRadiusUpdated(ChunkRadiusUpdatedPacket { chunk_radius })]).await?;
+
+    let spawn = session.get_level().spawn_position;
+    let (center_x, center_z) = (spawn.x >> 4, spawn.z >> 4);
+    let mut chunks = Vec::new();
+    for x in -chunk_radius..=chunk_radius {
+        for z in -chunk_radius..=chunk_radius {
+            if x * x + z * z > chunk_radius * chunk_radius {
+                continue;
+            }
+            chunks.push(Packet::LevelChunk(LevelChunkPacket {
+                chunk_position: ChunkPos {
+                    x: center_x + x,
+                    z: center_z + z,
+                },
+                dimension_id: 0,
+                cache_enabled: false,
+                cache_blobs: vec![],
+                serialized_chunk_data: String::new(),
+            }));
+        }
+    }
+    session.send(&chunks).await?;
+
+    if first_request {
+        session.send(&[Packet::PlayStatus(PlayStatusPacket {
+            status: PlayStatusType::PlayerSpawn,
+        })]).await?;
+    }
+    Ok(())
+}
+
+pub async fn handle_initialized(session: &mut BedrockSession, packet_data: &SetLocalPlayerAsInitializedPacket) -> Result<(), SessionError> {
+    if packet_data.target_actor_id.0 != session.get_entity_id() as u64 {
+        return Err(SessionError::disconnect("disconnectionScreen.noReason"));
+    }
+    session.change_state(SessionState::InGame)
+}
+
+fn build_start_game(session: &BedrockSession) -> StartGamePacket {
+    let properties = session.get_properties();
+    let level = session.get_level();
+    let entity_id = session.get_entity_id();
+    let spawn = level.spawn_position;
+
+    StartGamePacket {
+        target_actor_id: ActorUniqueID(entity_id),
+        target_runtime_id: ActorRuntimeID(entity_id as u64),
+        position: Vec3 {
+            x: spawn.x as f32 + 0.5,
+            y: spawn.y as f32,
+            z: spawn.z as f32 + 0.5,
+        },
+        rotation: Vec2 { x: 0.0, y: 0.0 },
+        settings: LevelSettings {
+            seed: level.seed,
+            spawn_settings: SpawnSettings {
+                biome_type: SpawnBiomeType::Default,
+                user_defined_biome_name: String::new(),
+                dimension: Dimension::Overworld,
+            },
+            generator_type: GeneratorType::Overworld,
+            game_type: level.game_type,
+            is_hardcore_mode_enabled: false,
+            game_difficulty: level.difficulty,
+            achievements_disabled: true,
+            editor_world_type: EditorWorldType::NotEditor,
+            is_created_in_editor: false,
+            day_cycle_stop_time: -1,
+            education_edition_offer: EducationEditionOffer::None,
+            education_product_id: String::new(),
+            rain_level: 0.0,
+            lightning_level: 0.0,
+            has_confirmed_platform_locked_content: false,
+            multiplayer_enabled: true,
+            lan_broadcasting_enabled: true,
+            xbox_live_broadcast_setting: GamePublishSetting::Public,
+            platform_broadcast_setting: GamePublishSetting::Public,
+            commands_enabled: true,
+            texture_packs_required: properties.force_resource_packs,
+            experiments: properties.get_experiments(),
+            bonus_chest_enabled: false,
+            starting_map_enabled: false,
+            player_permissions: abilities::get_player_permission(session.is_operator()),
+            server_chunk_tick_range: properties.view_distance,
+            locked_behaviour_pack: false,
+            from_locked_template: false,
+            from_template: false,
+            only_spawn_v1_villagers: false,
+            persona_disabled: false,
+            custom_skins_disabled: false,
+            emote_chat_muted: false,
+            base_game_version: BaseGameVersion(GAME_VERSION.to_string()),
+            limited_world_width: 16,
+            limited_world_depth: 16,
+            edu_shared_uri_resource: EduSharedResourceUri {
+                button_name: String::new(),
+                link_uri: String::new(),
+            },
+            chat_restriction_level: ChatRestrictionLevel::None,
+            disable_player_interactions: false,
+            server_identifier: String::new(),
+            server_world_identifier: String::new(),
+            default_spawn_block_position: NetworkBlockPosition {
+                x: spawn.x,
+                y: spawn.y as u32,
+                z: spawn.z,
+            },
+            is_exported_from_editor: false,
+            education_features_enabled: false,
+            rule_data: GameRulesChangedPacketData { rules_list: vec![] },
+            locked_resource_pack: false,
+            use_msa_gamer_tags: false,
+            has_locked_template_settings: false,
+            nether_type: false,
+            override_force_experimental_gameplay: false,
+            server_scenario_identifier: String::new(),
+        },
+        level_id: level.name.clone(),
+        level_name: level.name.clone(),
+        template_content_identity: String::new(),
+        movement_settings: SyncedPlayerMovementSettings {
+            authority_mode: ServerAuthMovementMode::ServerAuthoritative,
+            rewind_history_size: 0,
+            server_authoritative_block_breaking: true,
+        },
+        current_level_time: level.time,
+        enchantment_seed: 0,
+        block_properties: Vec::new(),
+        multiplayer_correlation_id: String::new(),
+        server_version: GAME_VERSION.to_string(),
+        player_property_data: nbtx::Value::Compound(HashMap::new()),
+        world_template_id: Uuid::nil(),
+        server_enabled_client_side_generation: false,
+        block_network_ids_are_hashes: true,
+        is_trial: false,
+        enable_item_stack_net_manager: false,
+        server_block_type_registry_checksum: 0,
+        network_permissions: NetworkPermissions {
+            server_auth_sound: false,
+        },
+        player_gamemode: properties.get_player_gamemode(),
+    }
+}
@@ -1,115 +1,120 @@
-use bedrockrs::addon::Addon;
-use bedrockrs::addon::resource::ResourcePack;
 use crate::network::connection::bedrock_session::{BedrockSession, SessionState};
+use crate::network::connection::session_error::SessionError;
+use crate::network::process::bedrock_session_handler::pre_spawn_handler;
+use crate::network::protocol::packet::Packet;
+use crate::network::protocol_info::GAME_VERSION;
+use crate::resource_pack::pack::ResourcePack;
 use bedrockrs::proto::v662::enums::{ConnectionFailReason, PackType, ResourcePackResponse};
 use bedrockrs::proto::v662::packets::{ClientToServerHandshakePacket, PlayStatusPacket, ResourcePackClientResponsePacket, ResourcePackDataInfoPacket};
 use bedrockrs::proto::v729::types::base_game_version::BaseGameVersion;
-use bedrockrs::proto::v729::types::experiments::{Experiment, Experiments};
 use bedrockrs::proto::v748::packets::{DisconnectPacket, DisconnectPacketMessage, PackEntry, ResourcePackStackPacket};
-use bedrockrs::proto::v766::packets::ResourcePacksInfoPacket;
-use bedrockrs::proto::v785::gamepackets::GamePackets;
-use log::{info, log};
+use bedrockrs::proto::v766::packets::{ResourcePackEntry, ResourcePacksInfoPacket};
+use log::{debug, info, log};
+use std::sync::Arc;
 use uuid::Uuid;
 use vek::num_traits::real::Real;
 
 pub async fn handle(
     mut session: &mut BedrockSession,
     packet_data: &ResourcePackClientResponsePacket,
-) {
-    println!("ResourcePackClientResponsePacket");
+) -> Result<(), SessionError> {
     match packet_data.response {
         ResourcePackResponse::Refused => {
-            println!("Refused");
-            session
-                .send(&[GamePackets::Disconnect(DisconnectPacket {
-                    reason: ConnectionFailReason::Unknown,
-                    messages: Some(DisconnectPacketMessage {
-                        message: String::from("disconnectionScreen.noReason"),
-                        filtered_message: String::new(),
-                    }),
-                })])
-                .await;
-            session.close().await;
+            debug!("Session {} refused the resource packs", session.get_entity_id());
+            if session.get_properties().force_resource_packs {
+                return Err(SessionError::disconnect("disconnectionScreen.resourcePack"));
+            }
+            send_resource_pack_stack(session, false).await?;
         }
         ResourcePackResponse::SendPacks => {
-            for uuid in &packet_data.downloading_packs {
-                let res = ResourcePack::import("./res/resource_pack.zip").unwrap();
-                // println!("res {:?}", res.sha256);
-                println!("Downloading pack: {}", uuid);
-                // if !res.manifest.header.uuid.to_string().eq(uuid) {
-                //     session.send(&[
-                //         GamePackets::Disconnect(DisconnectPacket {
-                //             reason: ConnectionFailReason::Unknown,
-                //             messages: Some(DisconnectPacketMessage {
-                //                 message: String::from("disconnectionScreen.resourcePack"),
-                //                 filtered_message: String::new(),
-                //             }),
-                //         }),
-                //     ]).await;
-                //     return;
-                // }
-
-                let num_chunks = ((res.get_pack_size() as f64) / (1024.0 * 100.0)).ceil() as u32;
+            let manager = session.get_resource_pack_manager();
+            for pack_id in &packet_data.downloading_packs {
+                debug!("Sending pack {} to session {}", pack_id, session.get_entity_id());
+                let Some(pack) = manager.get_pack_by_id(pack_id) else {
+                    return Err(SessionError::disconnect("disconnectionScreen.resourcePack"));
+                };
 
+                let chunk_size = manager.get_chunk_size();
                 let data_info_packet = ResourcePackDataInfoPacket {
-                    resource_name: uuid.clone(),
-                    chunk_size: 1024 * 100,
-                    chunk_amount: num_chunks,
-                    file_size: res.get_pack_size(),
-                    file_hash: res.sha256,
-                    is_premium: false,
-                    pack_type: PackType::Resources,
+                    resource_name: pack.get_pack_id(),
+                    chunk_size,
+                    chunk_amount: pack.get_chunk_count(chunk_size),
+                    file_size: pack.get_pack_size(),
+                    file_hash: pack.sha256.clone(),
+                    is_premium: pack.is_encrypted(),
+                    pack_type: if pack.is_behavior_pack() { PackType::Behavior } else { PackType::Resources },
                 };
-                session.send(&[GamePackets::ResourcePackDataInfo(data_info_packet.clone())]).await;
+                session.send(&[Packet::ResourcePackDataInfo(data_info_packet)]).await?;
             }
         }
         ResourcePackResponse::HaveAllPacks => {
-            // let resource_pack = ResourcePack {
-            //     manifest: AddonManifest {},
-            //     languages: Languages {},
-            // };
-            session
-                .send(&[GamePackets::ResourcePackStack(ResourcePackStackPacket {
-                    texture_pack_required: false,
-                    addon_list: vec![],
-                    texture_pack_list: vec![],
-                    base_game_version: BaseGameVersion(String::from("1.0")),
-                    experiments: Experiments {
-                        experiments: vec![
-                            Experiment {
-                                name: String::from("data_driven_items"),
-                                enabled: true,
-                            },
-                            Experiment {
-                                name: String::from("data_driven_biomes"),
-                                enabled: true,
-                            },
-                            Experiment {
-                                name: String::from("upcoming_creator_features"),
-                                enabled: true,
-                            },
-                            Experiment {
-                                name: String::from("gametest"),
-                                enabled: true,
-                            },
-                            Experiment {
-                                name: String::from("experimental_molang_features"),
-                                enabled: true,
-                            },
-                            Experiment {
-                                name: String::from("cameras"),
-                                enabled: true,
-                            },
-                        ],
-                        ever_toggled: true,
-                    },
-                    include_editor_packs: false,
-                })])
-                .await;
+            send_resource_pack_stack(session, true).await?;
         }
         ResourcePackResponse::Completed => {
             info!("ResourcePackClientResponsePacket STATUS_COMPLETED");
-            session.change_state(SessionState::PreSpawn);
+            session.change_state(SessionState::PreSpawn)?;
+            pre_spawn_handler::start(session).await?;
         }
     }
+    Ok(())
+}
+
+async fn send_resource_pack_stack(session: &mut BedrockSession, include_packs: bool) -> Result<(), SessionError> {
+    let manager = session.get_resource_pack_manager();
+    let (addon_list, texture_pack_list) = if include_packs {
+        (pack_entries(manager.get_behavior_packs()), pack_entries(manager.get_resource_packs()))
+    } else {
+        (vec![], vec![])
+    };
+
+    session
+        .send(&[Packet::ResourcePackStack(ResourcePackStackPacket {
+            texture_pack_required: session.get_properties().force_resource_packs,
+            addon_list,
+            texture_pack_list,
+            base_game_version: BaseGameVersion(GAME_VERSION.to_string()),
+            experiments: session.get_properties().get_experiments(),
+            include_editor_packs: false,
+        })])
+        .await
+}
+
+pub async fn send_resource_packs_info(session: &mut BedrockSession) -> Result<(), SessionError> {
+    session.change_state(SessionState::ResourcePack)?;
+    let manager = session.get_resource_pack_manager();
+    let resource_packs = manager
+        .get_packs()
+        .map(|pack| ResourcePackEntry {
+            id: pack.get_id(),
+            version: pack.get_version().to_string(),
+            size: pack.get_pack_size(),
+            content_key: pack.content_key.clone(),
+            sub_pack_name: String::new(),
+            content_identity: if pack.is_encrypted() { pack.get_id().to_string() } else { String::new() },
+            has_scripts: pack.has_scripts(),
+            is_addon_pack: pack.is_behavior_pack(),
+            is_ray_tracing_capable: false,
+            cdn_url: session.get_properties().get_pack_cdn_url(&pack.get_pack_id()),
+        })
+        .collect();
+    session.send(&[
+        Packet::ResourcePacksInfo(ResourcePacksInfoPacket {
+            resource_pack_required: session.get_properties().force_resource_packs,
+            has_addon_packs: !manager.get_behavior_packs().is_empty(),
+            has_scripts: manager.get_behavior_packs().iter().any(|pack| pack.has_scripts()),
+            world_template_uuid: Uuid::new_v4(),
+            resource_packs,
+            world_template_version: String::new(),
+        })
+    ]).await
+}
+
+fn pack_entries(packs: &[Arc<ResourcePack>]) -> Vec<PackEntry> {
+    packs.iter()
+        .map(|pack| PackEntry {
+            id: pack.get_id().to_string(),
+            version: pack.get_version().to_string(),
+            sub_pack_name: String::new(),
+        })
+        .collect()
 }
@@ -6,38 +6,35 @@ use bedrockrs::proto::v662::packets::{NetworkSettingsPacket, RequestNetworkSetti
 use bedrockrs::proto::v729::packets::play_status::PlayStatusPacket;
 use bedrockrs::proto::v729::types::play_status::PlayStatusType;
 use bedrockrs::proto::v748::packets::{DisconnectPacket, DisconnectPacketMessage};
-use bedrockrs::proto::v785::gamepackets::GamePackets;
-use bedrockrs::proto::v785::helper::ProtoHelperV785;
 use crate::network::connection::bedrock_session::{BedrockSession, SessionState};
-use crate::network::protocol_info::CURRENT_PROTOCOL;
+use crate::network::connection::session_error::SessionError;
+use crate::network::protocol::packet::Packet;
+use crate::network::protocol::ProtocolVersion;
 
-pub async fn handle(mut session: &mut BedrockSession, packet_data: &RequestNetworkSettingsPacket) {
+pub async fn handle(mut session: &mut BedrockSession, packet_data: &RequestNetworkSettingsPacket) -> Result<(), SessionError> {
     let protocol = packet_data.client_network_version;
-    if (protocol != CURRENT_PROTOCOL) {
-        let message = if protocol < CURRENT_PROTOCOL {"disconnectionScreen.outdatedClient"} else {"disconnectionScreen.outdatedServer"};
-        let status = if protocol < CURRENT_PROTOCOL {PlayStatusType::FailedClientOld} else {PlayStatusType::FailedServerOld};
+    let Some(version) = ProtocolVersion::from_protocol(protocol) else {
+        let outdated_client = protocol < ProtocolVersion::latest().protocol();
+        let message = if outdated_client {"disconnectionScreen.outdatedClient"} else {"disconnectionScreen.outdatedServer"};
+        let status = if outdated_client {PlayStatusType::FailedClientOld} else {PlayStatusType::FailedServerOld};
         session.send(&[
-            GamePackets::PlayStatus(PlayStatusPacket {
+            Packet::PlayStatus(PlayStatusPacket {
                 status
             }),
-            GamePackets::Disconnect(DisconnectPacket {
-                reason: ConnectionFailReason::Unknown,
-                messages: Some(DisconnectPacketMessage { message: String::from(message), filtered_message: String::new() })
-            })
         ])
-            .await;
-        session.close().await;
-        return
-    }
+            .await?;
+        return Err(SessionError::disconnect(message));
+    };
 
-    session.send(&[GamePackets::NetworkSettings(NetworkSettingsPacket {
+    session.switch_protocol(version);
+    session.send(&[Packet::NetworkSettings(NetworkSettingsPacket {
         compression_threshold: 1,
         compression_algorithm: PacketCompressionAlgorithm::ZLib,
         client_throttle_enabled: false,
         client_throttle_threshold: 0,
         client_throttle_scalar: 0.0,
     })])
-        .await;
+        .await?;
 
     let compression = Compression::Zlib {
         threshold: 256,
@@ -45,6 +42,5 @@ pub async fn handle(mut session: &mut BedrockSession, packet_data: &RequestNetwo
     };
 
     session.set_compression(compression).await;
-    println!("NetworkSettings");
-    session.change_state(SessionState::Login);
+    session.change_state(SessionState::Login)
 }
\ No newline at end of file
@@ -0,0 +1,43 @@
+pub mod packet;
+pub(crate) mod v776;
+pub(crate) mod v785;
+pub(crate) mod v786;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ProtocolVersion {
+    V776,
+    V785,
+    V786,
+}
+
+impl ProtocolVersion {
+    pub const SUPPORTED: [ProtocolVersion; 3] = [ProtocolVersion::V776, ProtocolVersion::V785, ProtocolVersion::V786];
+
+    pub fn from_protocol(protocol: i32) -> Option<ProtocolVersion> {
+        Self::SUPPORTED.into_iter().find(|version| version.protocol() == protocol)
+    }
+
+    pub fn oldest() -> ProtocolVersion {
+        Self::SUPPORTED[0]
+    }
+
+    pub fn latest() -> ProtocolVersion {
+        Self::SUPPORTED[Self::SUPPORTED.len() - 1]
+    }
+
+    pub fn protocol(&self) -> i32 {
+        match self {
+            ProtocolVersion::V776 => 776,
+            ProtocolVersion::V785 => 785,
+            ProtocolVersion::V786 => 786,
+        }
+    }
+
+    pub fn game_version(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V776 => "1.21.60",
+            ProtocolVersion::V785 => "1.21.70",
+            ProtocolVersion::V786 => "1.21.70",
+        }
+    }
+}
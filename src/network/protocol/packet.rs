@@ -0,0 +1,106 @@
+use bedrockrs::proto::v662::packets::{
+    AvailableCommandsPacket, ChunkRadiusUpdatedPacket, CommandRequestPacket, UpdateAbilitiesPacket, ClientToServerHandshakePacket, LevelChunkPacket, NetworkSettingsPacket,
+    RequestChunkRadiusPacket, RequestNetworkSettingsPacket, ResourcePackChunkDataPacket,
+    ResourcePackChunkRequestPacket, ResourcePackClientResponsePacket, ResourcePackDataInfoPacket,
+    ServerToClientHandshakePacket, SetLocalPlayerAsInitializedPacket, TextPacket,
+};
+use bedrockrs::proto::v729::packets::login::LoginPacket;
+use bedrockrs::proto::v729::packets::play_status::PlayStatusPacket;
+use bedrockrs::proto::v748::packets::{DisconnectPacket, ResourcePackStackPacket, UpdateAttributesPacket};
+use bedrockrs::proto::v766::packets::{PlayerListPacket, ResourcePacksInfoPacket};
+use bedrockrs::proto::v776::packets::{ItemRegistryPacket, StartGamePacket};
+use bedrockrs::proto::v785::packets::PlayerAuthInputPacket;
+
+/// Version-neutral packet model. Handlers and game logic only deal with this enum,
+/// the per-version modules translate it to and from the bedrockrs `GamePackets`.
+/// Payloads are the bedrockrs types of the newest version that changed them, a version
+/// with a different layout leaves the variant out of its mapping.
+#[derive(Debug, Clone)]
+pub enum Packet {
+    RequestNetworkSettings(RequestNetworkSettingsPacket),
+    NetworkSettings(NetworkSettingsPacket),
+    Login(LoginPacket),
+    PlayStatus(PlayStatusPacket),
+    ServerToClientHandshake(ServerToClientHandshakePacket),
+    ClientToServerHandshake(ClientToServerHandshakePacket),
+    Disconnect(DisconnectPacket),
+    ResourcePacksInfo(ResourcePacksInfoPacket),
+    ResourcePackStack(ResourcePackStackPacket),
+    ResourcePackClientResponse(ResourcePackClientResponsePacket),
+    ResourcePackDataInfo(ResourcePackDataInfoPacket),
+    ResourcePackChunkRequest(ResourcePackChunkRequestPacket),
+    ResourcePackChunkData(ResourcePackChunkDataPacket),
+    StartGame(StartGamePacket),
+    ItemRegistry(ItemRegistryPacket),
+    LevelChunk(LevelChunkPacket),
+    PlayerList(PlayerListPacket),
+    UpdateAttributes(UpdateAttributesPacket),
+    PlayerAuthInput(PlayerAuthInputPacket),
+    RequestChunkRadius(RequestChunkRadiusPacket),
+    ChunkRadiusUpdated(ChunkRadiusUpdatedPacket),
+    SetLocalPlayerAsInitialized(SetLocalPlayerAsInitializedPacket),
+    Text(TextPacket),
+    AvailableCommands(AvailableCommandsPacket),
+    CommandRequest(CommandRequestPacket),
+    UpdateAbilities(UpdateAbilitiesPacket),
+    /// A packet the neutral model does not cover yet, kept as its debug representation.
+    Unknown(String),
+}
+
+impl Packet {
+    /// The variant's name, `Unknown` for packets the model does not cover.
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Packet::RequestNetworkSettings(_) => "RequestNetworkSettings",
+            Packet::NetworkSettings(_) => "NetworkSettings",
+            Packet::Login(_) => "Login",
+            Packet::PlayStatus(_) => "PlayStatus",
+            Packet::ServerToClientHandshake(_) => "ServerToClientHandshake",
+            Packet::ClientToServerHandshake(_) => "ClientToServerHandshake",
+            Packet::Disconnect(_) => "Disconnect",
+            Packet::ResourcePacksInfo(_) => "ResourcePacksInfo",
+            Packet::ResourcePackStack(_) => "ResourcePackStack",
+            Packet::ResourcePackClientResponse(_) => "ResourcePackClientResponse",
+            Packet::ResourcePackDataInfo(_) => "ResourcePackDataInfo",
+            Packet::ResourcePackChunkRequest(_) => "ResourcePackChunkRequest",
+            Packet::ResourcePackChunkData(_) => "ResourcePackChunkData",
+            Packet::StartGame(_) => "StartGame",
+            Packet::ItemRegistry(_) => "ItemRegistry",
+            Packet::LevelChunk(_) => "LevelChunk",
+            Packet::PlayerList(_) => "PlayerList",
+            Packet::UpdateAttributes(_) => "UpdateAttributes",
+            Packet::PlayerAuthInput(_) => "PlayerAuthInput",
+            Packet::RequestChunkRadius(_) => "RequestChunkRadius",
+            Packet::ChunkRadiusUpdated(_) => "ChunkRadiusUpdated",
+            Packet::SetLocalPlayerAsInitialized(_) => "SetLocalPlayerAsInitialized",
+            Packet::Text(_) => "Text",
+            Packet::AvailableCommands(_) => "AvailableCommands",
+            Packet::CommandRequest(_) => "CommandRequest",
+            Packet::UpdateAbilities(_) => "UpdateAbilities",
+            Packet::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+/// Generates `decode`/`encode` between `Packet` and a version's `GamePackets`. Each
+/// version module lists the variants whose payload type is the one `Packet` stores, other
+/// variants decode to `Unknown` and are not sent to clients of that version.
+#[macro_export]
+macro_rules! game_packet_mapping {
+    ($game_packets:ident; $($name:ident),* $(,)?) => {
+        pub fn decode(packet: $game_packets) -> Packet {
+            match packet {
+                $($game_packets::$name(data) => Packet::$name(data),)*
+                other => Packet::Unknown(format!("{:?}", other)),
+            }
+        }
+
+        #[allow(unreachable_patterns)]
+        pub fn encode(packet: Packet) -> Option<$game_packets> {
+            match packet {
+                $(Packet::$name(data) => Some($game_packets::$name(data)),)*
+                _ => None,
+            }
+        }
+    };
+}
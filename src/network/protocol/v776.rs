@@ -0,0 +1,32 @@
+use bedrockrs::proto::v776::gamepackets::GamePackets;
+use crate::network::protocol::packet::Packet;
+
+// `PlayerAuthInput` changed in v785, v776 clients' input decodes to `Unknown`.
+crate::game_packet_mapping!(
+    GamePackets;
+    RequestNetworkSettings,
+    NetworkSettings,
+    Login,
+    PlayStatus,
+    ServerToClientHandshake,
+    ClientToServerHandshake,
+    Disconnect,
+    ResourcePacksInfo,
+    ResourcePackStack,
+    ResourcePackClientResponse,
+    ResourcePackDataInfo,
+    ResourcePackChunkRequest,
+    ResourcePackChunkData,
+    StartGame,
+    ItemRegistry,
+    LevelChunk,
+    PlayerList,
+    UpdateAttributes,
+    RequestChunkRadius,
+    ChunkRadiusUpdated,
+    SetLocalPlayerAsInitialized,
+    Text,
+    AvailableCommands,
+    CommandRequest,
+    UpdateAbilities,
+);
@@ -0,0 +1,32 @@
+use bedrockrs::proto::v785::gamepackets::GamePackets;
+use crate::network::protocol::packet::Packet;
+
+crate::game_packet_mapping!(
+    GamePackets;
+    RequestNetworkSettings,
+    NetworkSettings,
+    Login,
+    PlayStatus,
+    ServerToClientHandshake,
+    ClientToServerHandshake,
+    Disconnect,
+    ResourcePacksInfo,
+    ResourcePackStack,
+    ResourcePackClientResponse,
+    ResourcePackDataInfo,
+    ResourcePackChunkRequest,
+    ResourcePackChunkData,
+    StartGame,
+    ItemRegistry,
+    LevelChunk,
+    PlayerList,
+    UpdateAttributes,
+    PlayerAuthInput,
+    RequestChunkRadius,
+    ChunkRadiusUpdated,
+    SetLocalPlayerAsInitialized,
+    Text,
+    AvailableCommands,
+    CommandRequest,
+    UpdateAbilities,
+);
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use bedrockrs::proto::v662::enums::TextPacketType;
+use bedrockrs::proto::v662::packets::TextPacket;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use crate::network::protocol::packet::Packet;
+
+/// Sent from the tick or other sessions to a session task, which owns the connection.
+#[derive(Debug)]
+pub enum SessionCommand {
+    Send(Vec<Packet>),
+    Disconnect(String),
+}
+
+/// Sent from session tasks to the main tick, where game logic runs.
+#[derive(Debug)]
+pub enum SessionEvent {
+    Joined(Arc<SessionHandle>),
+    Packet(i64, Packet),
+    Left(Arc<SessionHandle>),
+}
+
+/// What the rest of the server sees of a logged in session.
+#[derive(Debug)]
+pub struct SessionHandle {
+    pub runtime_id: i64,
+    pub uuid: Uuid,
+    pub xuid: String,
+    pub display_name: String,
+    pub address: Option<SocketAddr>,
+    sender: mpsc::UnboundedSender<SessionCommand>,
+}
+
+impl SessionHandle {
+    pub fn new(
+        runtime_id: i64,
+        uuid: Uuid,
+        xuid: String,
+        display_name: String,
+        address: Option<SocketAddr>,
+        sender: mpsc::UnboundedSender<SessionCommand>,
+    ) -> SessionHandle {
+        SessionHandle { runtime_id, uuid, xuid, display_name, address, sender }
+    }
+
+    /// Queues packets on the session. Returns `false` if the session already ended.
+    pub fn send(&self, packets: Vec<Packet>) -> bool {
+        self.sender.send(SessionCommand::Send(packets)).is_ok()
+    }
+
+    pub fn disconnect(&self, message: &str) -> bool {
+        self.sender.send(SessionCommand::Disconnect(message.to_string())).is_ok()
+    }
+
+    /// Shows `message` in the client's chat as is.
+    pub fn send_message(&self, message: &str) -> bool {
+        self.send(vec![raw_text(message)])
+    }
+}
+
+fn raw_text(message: &str) -> Packet {
+    Packet::Text(TextPacket {
+        message_type: TextPacketType::Raw,
+        localize: false,
+        player_name: String::new(),
+        message: message.to_string(),
+        parameter_list: Vec::new(),
+        sender_xuid: String::new(),
+        platform_id: String::new(),
+        filtered_message: String::new(),
+    })
+}
+
+/// Logged in sessions by runtime ID and by client UUID. The locks are only held for map
+/// lookups, never across an await.
+#[derive(Default)]
+pub struct SessionRegistry {
+    sessions: RwLock<HashMap<i64, Arc<SessionHandle>>>,
+    uuids: RwLock<HashMap<Uuid, i64>>,
+}
+
+impl SessionRegistry {
+    /// Adds `handle`, returning the older session when the same UUID was already logged
+    /// in so the caller can kick it. The older session stays listed until it unregisters.
+    pub fn register(&self, handle: SessionHandle) -> Option<Arc<SessionHandle>> {
+        let handle = Arc::new(handle);
+        let mut sessions = self.sessions.write().unwrap_or_else(|err| err.into_inner());
+        let mut uuids = self.uuids.write().unwrap_or_else(|err| err.into_inner());
+
+        let replaced = uuids.insert(handle.uuid, handle.runtime_id)
+            .and_then(|runtime_id| sessions.get(&runtime_id).cloned());
+        sessions.insert(handle.runtime_id, handle);
+        replaced
+    }
+
+    pub fn unregister(&self, runtime_id: i64) -> Option<Arc<SessionHandle>> {
+        let mut sessions = self.sessions.write().unwrap_or_else(|err| err.into_inner());
+        let mut uuids = self.uuids.write().unwrap_or_else(|err| err.into_inner());
+
+        let handle = sessions.remove(&runtime_id)?;
+        if uuids.get(&handle.uuid) == Some(&runtime_id) {
+            uuids.remove(&handle.uuid);
+        }
+        Some(handle)
+    }
+
+    pub fn get(&self, runtime_id: i64) -> Option<Arc<SessionHandle>> {
+        self.sessions.read().unwrap_or_else(|err| err.into_inner()).get(&runtime_id).cloned()
+    }
+
+    pub fn get_by_uuid(&self, uuid: &Uuid) -> Option<Arc<SessionHandle>> {
+        let runtime_id = *self.uuids.read().unwrap_or_else(|err| err.into_inner()).get(uuid)?;
+        self.get(runtime_id)
+    }
+
+    pub fn get_by_name(&self, display_name: &str) -> Option<Arc<SessionHandle>> {
+        self.sessions.read().unwrap_or_else(|err| err.into_inner())
+            .values()
+            .find(|handle| handle.display_name.eq_ignore_ascii_case(display_name))
+            .cloned()
+    }
+
+    pub fn get_all(&self) -> Vec<Arc<SessionHandle>> {
+        self.sessions.read().unwrap_or_else(|err| err.into_inner()).values().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.read().unwrap_or_else(|err| err.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn broadcast(&self, packets: &[Packet]) {
+        for handle in self.get_all() {
+            handle.send(packets.to_vec());
+        }
+    }
+
+    pub fn broadcast_message(&self, message: &str) {
+        self.broadcast(&[raw_text(message)]);
+    }
+}
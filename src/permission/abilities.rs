@@ -0,0 +1,69 @@
+use bedrockrs::proto::v662::enums::{AbilitiesLayer, CommandPermissionLevel, GameType, PlayerPermissionLevel};
+use bedrockrs::proto::v662::packets::UpdateAbilitiesPacket;
+use bedrockrs::proto::v662::types::{SerializedAbilitiesData, SerializedLayer};
+use crate::command::PermissionLevel;
+use crate::network::protocol::packet::Packet;
+
+// Bits of the ability set, in the order of the protocol's `AbilitiesIndex`.
+const BUILD: u32 = 1 << 0;
+const MINE: u32 = 1 << 1;
+const DOORS_AND_SWITCHES: u32 = 1 << 2;
+const OPEN_CONTAINERS: u32 = 1 << 3;
+const ATTACK_PLAYERS: u32 = 1 << 4;
+const ATTACK_MOBS: u32 = 1 << 5;
+const OPERATOR_COMMANDS: u32 = 1 << 6;
+const TELEPORT: u32 = 1 << 7;
+const INVULNERABLE: u32 = 1 << 8;
+const FLYING: u32 = 1 << 9;
+const MAY_FLY: u32 = 1 << 10;
+const INSTABUILD: u32 = 1 << 11;
+const NO_CLIP: u32 = 1 << 17;
+const ALL_ABILITIES: u32 = (1 << 19) - 1;
+
+const DEFAULT_FLY_SPEED: f32 = 0.05;
+const DEFAULT_WALK_SPEED: f32 = 0.1;
+
+/// Builds the abilities of a player. Clients since 1.19.30 take their permissions from
+/// this packet alone, `AdventureSettings` is no longer read.
+pub fn build(runtime_id: i64, level: PermissionLevel, operator: bool, game_type: GameType) -> Packet {
+    let mut abilities = match game_type {
+        GameType::Adventure => DOORS_AND_SWITCHES | OPEN_CONTAINERS | ATTACK_PLAYERS | ATTACK_MOBS,
+        GameType::Spectator => FLYING | MAY_FLY | INVULNERABLE | NO_CLIP,
+        _ => BUILD | MINE | DOORS_AND_SWITCHES | OPEN_CONTAINERS | ATTACK_PLAYERS | ATTACK_MOBS,
+    };
+    if game_type == GameType::Creative {
+        abilities |= MAY_FLY | INVULNERABLE | INSTABUILD;
+    }
+    if operator {
+        abilities |= OPERATOR_COMMANDS | TELEPORT;
+    }
+
+    Packet::UpdateAbilities(UpdateAbilitiesPacket {
+        data: SerializedAbilitiesData {
+            target_player_raw_id: runtime_id,
+            player_permission: get_player_permission(operator),
+            command_permission: get_command_permission(level),
+            layers: vec![SerializedLayer {
+                serialized_layer: AbilitiesLayer::Base,
+                abilities_set: ALL_ABILITIES,
+                ability_values: abilities,
+                fly_speed: DEFAULT_FLY_SPEED,
+                walk_speed: DEFAULT_WALK_SPEED,
+            }],
+        },
+    })
+}
+
+pub fn get_player_permission(operator: bool) -> PlayerPermissionLevel {
+    if operator { PlayerPermissionLevel::Operator } else { PlayerPermissionLevel::Member }
+}
+
+fn get_command_permission(level: PermissionLevel) -> CommandPermissionLevel {
+    match level {
+        PermissionLevel::Any => CommandPermissionLevel::Any,
+        PermissionLevel::GameDirectors => CommandPermissionLevel::GameDirectors,
+        PermissionLevel::Admin => CommandPermissionLevel::Admin,
+        PermissionLevel::Host => CommandPermissionLevel::Host,
+        PermissionLevel::Owner => CommandPermissionLevel::Owner,
+    }
+}
@@ -0,0 +1,351 @@
+pub mod abilities;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use crate::command::PermissionLevel;
+use crate::utils::config::{Config, ConfigType};
+
+/// Group every player is in, besides the groups listed for them.
+const DEFAULT_GROUP: &str = "default";
+
+/// A player in `ops.json`. Entries with an XUID only match that XUID, entries without
+/// one match by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operator {
+    pub name: String,
+    #[serde(default)]
+    pub xuid: String,
+    #[serde(default = "default_operator_level")]
+    pub level: u8,
+}
+
+fn default_operator_level() -> u8 {
+    PermissionLevel::Owner as u8
+}
+
+impl Operator {
+    /// Whether a connected player is this operator. `LoginData` only keeps the XUID of a
+    /// chain signed by Xbox Live, so neither a name nor an unauthenticated login can claim
+    /// an XUID-bound entry.
+    fn matches(&self, xuid: &str, name: &str) -> bool {
+        if !self.xuid.is_empty() {
+            return self.xuid == xuid;
+        }
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Whether this is the entry an admin refers to, by XUID or by name.
+    fn is_listed_as(&self, xuid: &str, name: &str) -> bool {
+        (!self.xuid.is_empty() && self.xuid == xuid) || self.name.eq_ignore_ascii_case(name)
+    }
+
+    pub fn get_permission_level(&self) -> PermissionLevel {
+        PermissionLevel::from_u8(self.level)
+    }
+}
+
+/// A group in `permissions.yml`. Nodes prefixed with `-` are denied.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PermissionGroup {
+    #[serde(default)]
+    pub inherits: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Groups and nodes given to one player in `permissions.yml`, keyed by name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerPermissions {
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+/// Operators from `ops.json` and permission groups from `permissions.yml`.
+///
+/// Nodes are dotted strings such as `powercrabx.command.kick`. A node is looked up from
+/// most to least specific, `a.b.c`, `a.b.*`, `a.*`, `*`, and the first entry found
+/// decides. Player entries override their groups, later groups override earlier ones and
+/// a group overrides the groups it inherits. Operators hold every node.
+pub struct PermissionManager {
+    ops_path: PathBuf,
+    groups_path: PathBuf,
+    ops: RwLock<Vec<Operator>>,
+    groups: RwLock<HashMap<String, PermissionGroup>>,
+    players: RwLock<HashMap<String, PlayerPermissions>>,
+}
+
+impl Default for PermissionManager {
+    fn default() -> Self {
+        Self {
+            ops_path: PathBuf::new(),
+            groups_path: PathBuf::new(),
+            ops: RwLock::new(Vec::new()),
+            groups: RwLock::new(default_groups()),
+            players: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+impl PermissionManager {
+    pub fn load(data_path: &str) -> PermissionManager {
+        let manager = PermissionManager {
+            ops_path: Path::new(data_path).join("ops.json"),
+            groups_path: Path::new(data_path).join("permissions.yml"),
+            ..PermissionManager::default()
+        };
+        manager.reload();
+        manager
+    }
+
+    /// Reads both files again, keeping the current lists of a file that fails to load.
+    pub fn reload(&self) {
+        match load_ops(&self.ops_path) {
+            Ok(ops) => *self.ops.write().unwrap_or_else(|err| err.into_inner()) = ops,
+            Err(err) => error!("Failed to load {}: {}", self.ops_path.display(), err),
+        }
+        match load_groups(&self.groups_path) {
+            Ok((groups, players)) => {
+                *self.groups.write().unwrap_or_else(|err| err.into_inner()) = groups;
+                *self.players.write().unwrap_or_else(|err| err.into_inner()) = players;
+            }
+            Err(err) => error!("Failed to load {}: {}", self.groups_path.display(), err),
+        }
+        info!(
+            "Loaded {} operator(s) and {} permission group(s)",
+            self.ops.read().unwrap_or_else(|err| err.into_inner()).len(),
+            self.groups.read().unwrap_or_else(|err| err.into_inner()).len(),
+        );
+    }
+
+    pub fn get_operator(&self, xuid: &str, name: &str) -> Option<Operator> {
+        self.ops.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .find(|operator| operator.matches(xuid, name))
+            .cloned()
+    }
+
+    /// The `ops.json` entry for a player by XUID or name, for managing the list.
+    pub fn find_operator(&self, xuid: &str, name: &str) -> Option<Operator> {
+        self.ops.read().unwrap_or_else(|err| err.into_inner())
+            .iter()
+            .find(|operator| operator.is_listed_as(xuid, name))
+            .cloned()
+    }
+
+    pub fn is_operator(&self, xuid: &str, name: &str) -> bool {
+        self.get_operator(xuid, name).is_some()
+    }
+
+    pub fn get_operators(&self) -> Vec<Operator> {
+        self.ops.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    /// Command permission level, `Any` for players that are no operator.
+    pub fn get_permission_level(&self, xuid: &str, name: &str) -> PermissionLevel {
+        self.get_operator(xuid, name)
+            .map_or(PermissionLevel::Any, |operator| operator.get_permission_level())
+    }
+
+    /// Adds or updates an operator and saves `ops.json`.
+    pub fn add_operator(&self, operator: Operator) -> Result<(), String> {
+        let mut ops = self.ops.write().unwrap_or_else(|err| err.into_inner());
+        ops.retain(|existing| !existing.is_listed_as(&operator.xuid, &operator.name));
+        ops.push(operator);
+        save_ops(&self.ops_path, &ops)
+    }
+
+    /// Removes an operator and saves `ops.json`. Returns `false` if there was none.
+    pub fn remove_operator(&self, xuid: &str, name: &str) -> Result<bool, String> {
+        let mut ops = self.ops.write().unwrap_or_else(|err| err.into_inner());
+        let count = ops.len();
+        ops.retain(|operator| !operator.is_listed_as(xuid, name));
+        if ops.len() == count {
+            return Ok(false);
+        }
+        save_ops(&self.ops_path, &ops).map(|_| true)
+    }
+
+    pub fn get_groups(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.groups.read().unwrap_or_else(|err| err.into_inner()).keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Whether the player holds `node`. Nodes nobody granted are denied.
+    pub fn has_permission(&self, xuid: &str, name: &str, node: &str) -> bool {
+        if self.is_operator(xuid, name) {
+            return true;
+        }
+        let nodes = self.resolve(name);
+        let node = node.to_lowercase();
+        for candidate in node_candidates(&node) {
+            if let Some(allowed) = nodes.get(&candidate) {
+                return *allowed;
+            }
+        }
+        false
+    }
+
+    /// Effective nodes of a player, `true` for granted and `false` for denied.
+    fn resolve(&self, name: &str) -> HashMap<String, bool> {
+        let groups = self.groups.read().unwrap_or_else(|err| err.into_inner());
+        let players = self.players.read().unwrap_or_else(|err| err.into_inner());
+        let player = players.iter()
+            .find(|(player, _)| player.eq_ignore_ascii_case(name))
+            .map(|(_, player)| player);
+
+        let mut nodes = HashMap::new();
+        let mut visited = HashSet::new();
+        apply_group(&groups, DEFAULT_GROUP, &mut nodes, &mut visited);
+        if let Some(player) = player {
+            for group in &player.groups {
+                apply_group(&groups, group, &mut nodes, &mut visited);
+            }
+            apply_nodes(&player.permissions, &mut nodes);
+        }
+        nodes
+    }
+}
+
+/// Applies the inherited groups first so the group's own nodes override them. Each group is
+/// applied once, which also stops inheritance cycles.
+fn apply_group(
+    groups: &HashMap<String, PermissionGroup>,
+    name: &str,
+    nodes: &mut HashMap<String, bool>,
+    visited: &mut HashSet<String>,
+) {
+    let name = name.to_lowercase();
+    if !visited.insert(name.clone()) {
+        return;
+    }
+    let Some(group) = groups.get(&name) else {
+        warn!("Unknown permission group {}", name);
+        return;
+    };
+    for parent in &group.inherits {
+        apply_group(groups, parent, nodes, visited);
+    }
+    apply_nodes(&group.permissions, nodes);
+}
+
+fn apply_nodes(permissions: &[String], nodes: &mut HashMap<String, bool>) {
+    for permission in permissions {
+        let permission = permission.trim().to_lowercase();
+        match permission.strip_prefix('-') {
+            Some(denied) => nodes.insert(denied.to_string(), false),
+            None => nodes.insert(permission, true),
+        };
+    }
+}
+
+/// `a.b.c` followed by the wildcards covering it, most specific first.
+fn node_candidates(node: &str) -> Vec<String> {
+    let mut candidates = vec![node.to_string()];
+    let mut prefix = node;
+    while let Some(index) = prefix.rfind('.') {
+        prefix = &prefix[..index];
+        candidates.push(format!("{}.*", prefix));
+    }
+    candidates.push(String::from("*"));
+    candidates
+}
+
+fn default_groups() -> HashMap<String, PermissionGroup> {
+    HashMap::from([(
+        DEFAULT_GROUP.to_string(),
+        PermissionGroup {
+            inherits: Vec::new(),
+            permissions: vec![
+                String::from("powercrabx.command.help"),
+                String::from("powercrabx.command.list"),
+            ],
+        },
+    )])
+}
+
+fn load_ops(path: &Path) -> Result<Vec<Operator>, String> {
+    if !path.exists() {
+        save_ops(path, &[])?;
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&content).map_err(|e| format!("Invalid ops list: {}", e))
+}
+
+fn save_ops(path: &Path, ops: &[Operator]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(ops).map_err(|e| format!("Failed to serialize: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+type Groups = (HashMap<String, PermissionGroup>, HashMap<String, PlayerPermissions>);
+
+/// Reads `groups` and `players` from `permissions.yml`, writing the default groups when
+/// the file has none yet.
+fn load_groups(path: &Path) -> Result<Groups, String> {
+    let mut config = Config::new(Some(path.to_string_lossy().to_string()), ConfigType::Yaml);
+    config.load()?;
+
+    if !config.contains("groups") {
+        let groups: BTreeMap<String, PermissionGroup> = default_groups().into_iter().collect();
+        config.set_as("groups", &groups)?;
+        config.set("players", Value::Mapping(Default::default()));
+        config.save()?;
+    }
+
+    let groups: HashMap<String, PermissionGroup> = config.get_as("groups")?.unwrap_or_default();
+    let players = config.get_as("players")?.unwrap_or_default();
+
+    let groups = groups.into_iter().map(|(name, group)| (name.to_lowercase(), group)).collect();
+    Ok((groups, players))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XUID: &str = "2535400000000001";
+
+    fn manager_with(operator: Operator) -> PermissionManager {
+        let manager = PermissionManager::default();
+        manager.ops.write().unwrap().push(operator);
+        manager
+    }
+
+    #[test]
+    fn xuid_bound_operator_needs_the_xuid() {
+        let manager = manager_with(Operator { name: String::from("Owner"), xuid: XUID.to_string(), level: 4 });
+        assert!(manager.is_operator(XUID, "Owner"));
+        assert!(manager.is_operator(XUID, "Renamed"));
+
+        // Unauthenticated logins have no XUID, other accounts have their own.
+        assert!(!manager.is_operator("", "Owner"));
+        assert!(!manager.is_operator("2535400000000002", "Owner"));
+        assert_eq!(manager.get_permission_level("", "Owner"), PermissionLevel::Any);
+    }
+
+    #[test]
+    fn name_only_operator_matches_by_name() {
+        let manager = manager_with(Operator { name: String::from("Owner"), xuid: String::new(), level: 4 });
+        assert!(manager.is_operator("", "owner"));
+        assert!(manager.is_operator(XUID, "Owner"));
+        assert!(!manager.is_operator(XUID, "Renamed"));
+    }
+
+    #[test]
+    fn operator_is_managed_by_xuid_or_name() {
+        let manager = manager_with(Operator { name: String::from("Owner"), xuid: XUID.to_string(), level: 4 });
+        assert!(manager.find_operator("", "Owner").is_some());
+        assert!(manager.find_operator(XUID, "Renamed").is_some());
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+
+pub const MANIFEST_FILE: &str = "plugin.yml";
+
+/// `plugin.yml` at the root of a plugin folder.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    /// Server API version the plugin was built against, see `protocol_info::API_VERSION`.
+    pub api_version: String,
+    /// WebAssembly module, relative to the plugin folder.
+    #[serde(default = "default_main")]
+    pub main: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Plugins that must be enabled first, the plugin is skipped without them.
+    #[serde(default)]
+    pub depend: Vec<String>,
+    /// Plugins that are enabled first when they are installed.
+    #[serde(default)]
+    pub soft_depend: Vec<String>,
+}
+
+fn default_main() -> String {
+    String::from("plugin.wasm")
+}
+
+impl PluginManifest {
+    pub fn load(plugin_path: &Path) -> Result<PluginManifest, String> {
+        let path = plugin_path.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let manifest: PluginManifest = serde_yaml::from_str(&content)
+            .map_err(|e| format!("Invalid {}: {}", path.display(), e))?;
+        if manifest.name.is_empty() || manifest.name.contains(char::is_whitespace) {
+            return Err(format!("Invalid plugin name \"{}\" in {}", manifest.name, path.display()));
+        }
+        Ok(manifest)
+    }
+
+    pub fn get_full_name(&self) -> String {
+        format!("{} v{}", self.name, self.version)
+    }
+}
@@ -0,0 +1,226 @@
+pub mod manifest;
+pub mod wasm;
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::{error, info, warn};
+use crate::command::registry::CommandRegistry;
+use crate::network::protocol_info::API_VERSION;
+use crate::plugin::manifest::PluginManifest;
+use crate::plugin::wasm::WasmPlugin;
+use crate::server::{Server, ServerContext};
+use crate::utils::sem_version::SemVersion;
+
+/// Lifecycle hooks of a plugin. `on_load` runs for every plugin before the first one is
+/// enabled, `on_enable` in dependency order and `on_disable` in reverse order on shutdown.
+/// A plugin whose `on_load` or `on_enable` fails is not enabled.
+pub trait Plugin: Send {
+    fn get_manifest(&self) -> &PluginManifest;
+
+    fn on_load(&mut self, _server: &mut Server) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn on_enable(&mut self, _server: &mut Server) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Runs once per tick while the plugin is enabled.
+    fn on_tick(&mut self, _server: &mut Server) {}
+
+    /// Whether the plugin broke while running and already removed what it registered. The
+    /// manager then disables it without calling `on_disable`.
+    fn has_failed(&self) -> bool {
+        false
+    }
+
+    fn on_disable(&mut self, _server: &mut Server) {}
+}
+
+struct LoadedPlugin {
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+/// Plugins in enable order. The server takes the manager out of itself while calling
+/// hooks, so plugins get the whole `Server`.
+#[derive(Default)]
+pub struct PluginManager {
+    plugins: Vec<LoadedPlugin>,
+}
+
+impl PluginManager {
+    /// Loads every plugin folder in `plugins_path`. Plugins with a broken manifest, an
+    /// incompatible API version or a missing dependency are logged and skipped.
+    pub fn load(plugins_path: &Path, context: &ServerContext, commands: Arc<CommandRegistry>) -> PluginManager {
+        let mut manifests = Vec::new();
+        for entry in list_directory(plugins_path) {
+            match PluginManifest::load(&entry) {
+                Ok(manifest) => manifests.push((entry, manifest)),
+                Err(err) => error!("Failed to load plugin: {}", err),
+            }
+        }
+
+        let server_version = SemVersion::parse(API_VERSION).unwrap_or(SemVersion::new(0, 0, 0, 0, 0));
+        manifests.retain(|(_, manifest)| match SemVersion::parse(&manifest.api_version) {
+            Some(version) if version.is_compatible_with(&server_version) => true,
+            _ => {
+                error!(
+                    "Plugin {} needs API version {}, the server provides {}",
+                    manifest.get_full_name(), manifest.api_version, API_VERSION,
+                );
+                false
+            }
+        });
+
+        let mut manager = PluginManager::default();
+        for (path, manifest) in sort_by_dependencies(manifests) {
+            let missing: Vec<&String> = manifest.depend.iter()
+                .filter(|dependency| manager.get(dependency).is_none())
+                .collect();
+            if !missing.is_empty() {
+                error!("Plugin {} is missing dependencies: {:?}", manifest.get_full_name(), missing);
+                continue;
+            }
+            match WasmPlugin::load(&path, manifest, context.clone(), commands.clone()) {
+                Ok(plugin) => manager.register(Box::new(plugin)),
+                Err(err) => error!("Failed to load plugin {}: {}", path.display(), err),
+            }
+        }
+
+        info!("Loaded {} plugin(s)", manager.plugins.len());
+        manager
+    }
+
+    /// Adds a plugin built into the server. Must happen before `enable_all`.
+    pub fn register(&mut self, plugin: Box<dyn Plugin>) {
+        if self.get(&plugin.get_manifest().name).is_some() {
+            warn!("Plugin {} is already registered", plugin.get_manifest().name);
+            return;
+        }
+        self.plugins.push(LoadedPlugin { plugin, enabled: false });
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Plugin> {
+        self.plugins.iter()
+            .find(|loaded| loaded.plugin.get_manifest().name.eq_ignore_ascii_case(name))
+            .map(|loaded| loaded.plugin.as_ref())
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.plugins.iter()
+            .any(|loaded| loaded.enabled && loaded.plugin.get_manifest().name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn get_manifests(&self) -> Vec<&PluginManifest> {
+        self.plugins.iter().map(|loaded| loaded.plugin.get_manifest()).collect()
+    }
+
+    /// Runs `on_load` for every plugin, then `on_enable` for the ones whose hard
+    /// dependencies are enabled.
+    pub fn enable_all(&mut self, server: &mut Server) {
+        let mut loaded = Vec::new();
+        for (index, entry) in self.plugins.iter_mut().enumerate() {
+            match entry.plugin.on_load(server) {
+                Ok(()) => loaded.push(index),
+                Err(err) => error!("Failed to load plugin {}: {}", entry.plugin.get_manifest().get_full_name(), err),
+            }
+        }
+
+        for index in loaded {
+            let missing = self.plugins[index].plugin.get_manifest().depend.iter()
+                .any(|dependency| !self.is_enabled(dependency));
+            let entry = &mut self.plugins[index];
+            let name = entry.plugin.get_manifest().get_full_name();
+            if missing {
+                error!("Not enabling {}, a dependency failed to enable", name);
+                continue;
+            }
+            match entry.plugin.on_enable(server) {
+                Ok(()) => {
+                    entry.enabled = true;
+                    info!("Enabled {}", name);
+                }
+                Err(err) => error!("Failed to enable {}: {}", name, err),
+            }
+        }
+    }
+
+    pub fn tick(&mut self, server: &mut Server) {
+        for entry in self.plugins.iter_mut().filter(|entry| entry.enabled) {
+            if entry.plugin.has_failed() {
+                entry.enabled = false;
+                warn!("Disabled {} after it failed", entry.plugin.get_manifest().get_full_name());
+                continue;
+            }
+            entry.plugin.on_tick(server);
+        }
+    }
+
+    /// Disables plugins in reverse enable order, so dependents go first.
+    pub fn disable_all(&mut self, server: &mut Server) {
+        for entry in self.plugins.iter_mut().rev().filter(|entry| entry.enabled) {
+            entry.plugin.on_disable(server);
+            entry.enabled = false;
+            info!("Disabled {}", entry.plugin.get_manifest().get_full_name());
+        }
+    }
+}
+
+fn list_directory(path: &Path) -> Vec<PathBuf> {
+    if !path.exists() {
+        if let Err(err) = fs::create_dir_all(path) {
+            error!("Failed to create {}: {}", path.display(), err);
+        }
+    }
+    match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).filter(|path| path.is_dir()).collect(),
+        Err(err) => {
+            error!("Failed to read {}: {}", path.display(), err);
+            Vec::new()
+        }
+    }
+}
+
+/// Orders plugins so hard and soft dependencies come before their dependents.
+fn sort_by_dependencies(manifests: Vec<(PathBuf, PluginManifest)>) -> Vec<(PathBuf, PluginManifest)> {
+    let mut sorted = Vec::with_capacity(manifests.len());
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut in_progress: HashSet<String> = HashSet::new();
+
+    fn visit(
+        index: usize,
+        manifests: &[(PathBuf, PluginManifest)],
+        sorted: &mut Vec<usize>,
+        visited: &mut HashSet<String>,
+        in_progress: &mut HashSet<String>,
+    ) {
+        let name = manifests[index].1.name.to_lowercase();
+        if visited.contains(&name) {
+            return;
+        }
+        if !in_progress.insert(name.clone()) {
+            warn!("Dependency cycle detected at plugin {}", name);
+            return;
+        }
+        let manifest = &manifests[index].1;
+        for dependency in manifest.depend.iter().chain(&manifest.soft_depend) {
+            if let Some(dependency) = manifests.iter().position(|(_, manifest)| manifest.name.eq_ignore_ascii_case(dependency)) {
+                visit(dependency, manifests, sorted, visited, in_progress);
+            }
+        }
+        in_progress.remove(&name);
+        if visited.insert(name) {
+            sorted.push(index);
+        }
+    }
+
+    for index in 0..manifests.len() {
+        visit(index, &manifests, &mut sorted, &mut visited, &mut in_progress);
+    }
+
+    let mut manifests: Vec<Option<(PathBuf, PluginManifest)>> = manifests.into_iter().map(Some).collect();
+    sorted.into_iter().filter_map(|index| manifests[index].take()).collect()
+}
@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::{Arc, Mutex, TryLockError, Weak};
+use anyhow::anyhow;
+use log::{debug, error, info, warn};
+use serde_json::json;
+use wasmtime::{Caller, Engine, Extern, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use crate::command::{Command, PermissionLevel};
+use crate::command::parameter::{CommandParameter, ParameterType};
+use crate::command::registry::CommandRegistry;
+use crate::event::{Event, EventPriority};
+use crate::event::network::{PacketReceiveEvent, PacketSendEvent};
+use crate::event::player::{PlayerChatEvent, PlayerJoinEvent, PlayerPreLoginEvent, PlayerQuitEvent};
+use crate::event::server::ServerTickEvent;
+use crate::plugin::Plugin;
+use crate::plugin::manifest::PluginManifest;
+use crate::scheduler::TaskHandle;
+use crate::server::{Server, ServerContext};
+
+/// Host functions are imported from this module.
+const HOST_MODULE: &str = "powercrabx";
+/// Instructions a single call into a plugin may run before it is aborted.
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+// Event kinds passed to `subscribe` and `on_event`.
+const EVENT_PLAYER_JOIN: i32 = 0;
+const EVENT_PLAYER_QUIT: i32 = 1;
+const EVENT_PLAYER_CHAT: i32 = 2;
+const EVENT_SERVER_TICK: i32 = 3;
+const EVENT_PLAYER_PRE_LOGIN: i32 = 4;
+const EVENT_PACKET_RECEIVE: i32 = 5;
+const EVENT_PACKET_SEND: i32 = 6;
+
+/// A plugin compiled to WebAssembly. It runs without WASI, so it cannot reach files,
+/// sockets or the environment, and every call is bounded by fuel and a memory limit. A
+/// call that traps, including running out of fuel, disables the plugin and removes
+/// everything it registered.
+///
+/// Exports, all optional except `memory`:
+/// - `alloc(len: i32) -> i32`, needed to receive commands and events
+/// - `on_load()`, `on_enable()`, `on_disable()`
+/// - `on_command(id: i32, ptr: i32, len: i32) -> i32`, non-zero reports a failure
+/// - `on_event(kind: i32, ptr: i32, len: i32) -> i32`, non-zero cancels the event
+/// - `on_task(id: i32)`
+///
+/// Event kinds are player join `0`, quit `1`, chat `2`, server tick `3`, pre-login `4`,
+/// packet receive `5` and packet send `6`. Packet events only carry the packet's name and
+/// are delivered from the session tasks without waiting: while the plugin is busy, usually
+/// in a call on the tick, it misses them instead of stalling the tokio workers.
+///
+/// Imports from `powercrabx`:
+/// - `log(level: i32, ptr: i32, len: i32)`, levels 0 to 3 are debug, info, warn, error
+/// - `broadcast(ptr: i32, len: i32)`
+/// - `send_message(name_ptr: i32, name_len: i32, ptr: i32, len: i32) -> i32`, an empty
+///   name prints on the console
+/// - `register_command(name_ptr: i32, name_len: i32, description_ptr: i32,
+///   description_len: i32, permission: i32, id: i32) -> i32`
+/// - `subscribe(kind: i32, priority: i32) -> i32`
+/// - `schedule(delay: i64, period: i64, id: i32) -> i32`, a period of `0` runs once,
+///   returns a handle for `cancel_task`
+/// - `cancel_task(handle: i32) -> i32`
+///
+/// Commands and events are passed as JSON.
+pub struct WasmPlugin {
+    manifest: PluginManifest,
+    runtime: Arc<Mutex<WasmRuntime>>,
+}
+
+struct WasmRuntime {
+    store: Store<HostState>,
+    instance: Instance,
+}
+
+struct HostState {
+    name: String,
+    context: ServerContext,
+    commands: Arc<CommandRegistry>,
+    limits: StoreLimits,
+    runtime: Weak<Mutex<WasmRuntime>>,
+    registered_commands: Vec<String>,
+    listeners: Vec<u64>,
+    /// `schedule` calls waiting for the next tick, where the scheduler is reachable, as
+    /// handle, delay, period and task id.
+    pending_tasks: Vec<(i32, i64, i64, i32)>,
+    tasks: HashMap<i32, TaskHandle>,
+    next_task: i32,
+    /// Set once a call trapped, the plugin is not called again.
+    failed: bool,
+}
+
+impl HostState {
+    /// Removes everything the plugin registered, so nothing calls into it afterwards.
+    fn release(&mut self) {
+        for id in self.listeners.drain(..) {
+            self.context.event_bus.unsubscribe(id);
+        }
+        for name in self.registered_commands.drain(..) {
+            self.commands.unregister(&name);
+        }
+        self.pending_tasks.clear();
+        for (_, task) in self.tasks.drain() {
+            task.cancel();
+        }
+    }
+}
+
+impl WasmPlugin {
+    pub fn load(
+        plugin_path: &Path,
+        manifest: PluginManifest,
+        context: ServerContext,
+        commands: Arc<CommandRegistry>,
+    ) -> Result<WasmPlugin, String> {
+        let mut config = wasmtime::Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| format!("Failed to create engine: {}", e))?;
+
+        let module_path = plugin_path.join(&manifest.main);
+        let module = Module::from_file(&engine, &module_path)
+            .map_err(|e| format!("Failed to compile {}: {}", module_path.display(), e))?;
+
+        let mut linker = Linker::new(&engine);
+        add_host_functions(&mut linker).map_err(|e| format!("Failed to link host functions: {}", e))?;
+
+        let state = HostState {
+            name: manifest.name.clone(),
+            context,
+            commands,
+            limits: StoreLimitsBuilder::new().memory_size(MAX_MEMORY).instances(1).build(),
+            runtime: Weak::new(),
+            registered_commands: Vec::new(),
+            listeners: Vec::new(),
+            pending_tasks: Vec::new(),
+            tasks: HashMap::new(),
+            next_task: 0,
+            failed: false,
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let instance = linker.instantiate(&mut store, &module)
+            .map_err(|e| format!("Failed to instantiate {}: {}", module_path.display(), e))?;
+
+        let runtime = Arc::new(Mutex::new(WasmRuntime { store, instance }));
+        lock(&runtime).store.data_mut().runtime = Arc::downgrade(&runtime);
+
+        Ok(WasmPlugin { manifest, runtime })
+    }
+
+    /// Hands `schedule` calls made since the last tick to the scheduler.
+    fn schedule_pending(&self, server: &mut Server) {
+        let mut runtime = lock(&self.runtime);
+        let state = runtime.store.data_mut();
+        for (handle, delay, period, id) in std::mem::take(&mut state.pending_tasks) {
+            let task_runtime = self.runtime.clone();
+            let task = move |_: &mut Server| {
+                let mut runtime = lock(&task_runtime);
+                runtime.call_void("on_task", id);
+                if period == 0 {
+                    runtime.store.data_mut().tasks.remove(&handle);
+                }
+            };
+            let scheduler = server.get_scheduler();
+            let task = if period > 0 {
+                scheduler.run_repeating(delay, period, task)
+            } else {
+                scheduler.run_later(delay, task)
+            };
+            state.tasks.insert(handle, task);
+        }
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn get_manifest(&self) -> &PluginManifest {
+        &self.manifest
+    }
+
+    fn on_load(&mut self, _server: &mut Server) -> Result<(), String> {
+        lock(&self.runtime).call_hook("on_load")
+    }
+
+    fn on_enable(&mut self, server: &mut Server) -> Result<(), String> {
+        lock(&self.runtime).call_hook("on_enable")?;
+        self.schedule_pending(server);
+        Ok(())
+    }
+
+    fn on_tick(&mut self, server: &mut Server) {
+        self.schedule_pending(server);
+    }
+
+    fn has_failed(&self) -> bool {
+        lock(&self.runtime).store.data().failed
+    }
+
+    fn on_disable(&mut self, _server: &mut Server) {
+        let mut runtime = lock(&self.runtime);
+        if let Err(err) = runtime.call_hook("on_disable") {
+            error!("{}", err);
+        }
+        runtime.store.data_mut().release();
+    }
+}
+
+impl WasmRuntime {
+    /// Calls a lifecycle export if the plugin has it.
+    fn call_hook(&mut self, name: &str) -> Result<(), String> {
+        if self.store.data().failed {
+            return Ok(());
+        }
+        let Ok(hook) = self.instance.get_typed_func::<(), ()>(&mut self.store, name) else {
+            return Ok(());
+        };
+        self.refuel();
+        hook.call(&mut self.store, ()).map_err(|err| self.fail(name, err))
+    }
+
+    fn call_void(&mut self, name: &str, id: i32) {
+        if self.store.data().failed {
+            return;
+        }
+        let Ok(function) = self.instance.get_typed_func::<i32, ()>(&mut self.store, name) else {
+            return;
+        };
+        self.refuel();
+        if let Err(err) = function.call(&mut self.store, id) {
+            error!("{}", self.fail(name, err));
+        }
+    }
+
+    /// Copies `data` into the plugin's memory and calls `name(value, ptr, len)`. Returns
+    /// `None` if the plugin lacks the export or trapped.
+    fn call_with_data(&mut self, name: &str, value: i32, data: &str) -> Option<i32> {
+        if self.store.data().failed {
+            return None;
+        }
+        let function = self.instance.get_typed_func::<(i32, i32, i32), i32>(&mut self.store, name).ok()?;
+        let alloc = self.instance.get_typed_func::<i32, i32>(&mut self.store, "alloc").ok()?;
+        let memory = self.instance.get_memory(&mut self.store, "memory")?;
+        self.refuel();
+
+        let result = (|| {
+            let len = data.len() as i32;
+            let ptr = alloc.call(&mut self.store, len)?;
+            memory.write(&mut self.store, ptr as u32 as usize, data.as_bytes())?;
+            function.call(&mut self.store, (value, ptr, len))
+        })();
+        match result {
+            Ok(result) => Some(result),
+            Err(err) => {
+                error!("{}", self.fail(name, err));
+                None
+            }
+        }
+    }
+
+    /// Disables the plugin after a trap. Its memory may be left in any state, and a call
+    /// that ran out of fuel would do so again on every tick or event.
+    fn fail(&mut self, name: &str, err: impl Display) -> String {
+        let state = self.store.data_mut();
+        state.failed = true;
+        state.release();
+        format!("{} of {} failed, disabling it: {}", name, state.name, err)
+    }
+
+    fn refuel(&mut self) {
+        if let Err(err) = self.store.set_fuel(FUEL_PER_CALL) {
+            warn!("Failed to refuel {}: {}", self.store.data().name, err);
+        }
+    }
+}
+
+fn lock(runtime: &Mutex<WasmRuntime>) -> std::sync::MutexGuard<'_, WasmRuntime> {
+    runtime.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+fn read_string(caller: &mut Caller<'_, HostState>, ptr: i32, len: i32) -> anyhow::Result<String> {
+    let memory = caller.get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| anyhow!("plugin exports no memory"))?;
+    let (start, len) = (ptr as u32 as usize, len as u32 as usize);
+    let bytes = memory.data(&caller)
+        .get(start..start.saturating_add(len))
+        .ok_or_else(|| anyhow!("string out of bounds"))?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn add_host_functions(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+    linker.func_wrap(HOST_MODULE, "log", |mut caller: Caller<'_, HostState>, level: i32, ptr: i32, len: i32| {
+        let message = read_string(&mut caller, ptr, len)?;
+        let name = &caller.data().name;
+        match level {
+            0 => debug!("[{}] {}", name, message),
+            1 => info!("[{}] {}", name, message),
+            2 => warn!("[{}] {}", name, message),
+            _ => error!("[{}] {}", name, message),
+        }
+        Ok(())
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "broadcast", |mut caller: Caller<'_, HostState>, ptr: i32, len: i32| {
+        let message = read_string(&mut caller, ptr, len)?;
+        caller.data().context.sessions.broadcast_message(&message);
+        Ok(())
+    })?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "send_message",
+        |mut caller: Caller<'_, HostState>, name_ptr: i32, name_len: i32, ptr: i32, len: i32| {
+            let name = read_string(&mut caller, name_ptr, name_len)?;
+            let message = read_string(&mut caller, ptr, len)?;
+            if name.is_empty() {
+                info!("[{}] {}", caller.data().name, message);
+                return Ok(1);
+            }
+            let sent = caller.data().context.sessions.get_by_name(&name)
+                .is_some_and(|session| session.send_message(&message));
+            Ok(sent as i32)
+        },
+    )?;
+
+    linker.func_wrap(
+        HOST_MODULE,
+        "register_command",
+        |mut caller: Caller<'_, HostState>, name_ptr: i32, name_len: i32, description_ptr: i32, description_len: i32, permission: i32, id: i32| {
+            let name = read_string(&mut caller, name_ptr, name_len)?.to_lowercase();
+            let description = read_string(&mut caller, description_ptr, description_len)?;
+            let state = caller.data_mut();
+            if state.commands.get(&name).is_some() {
+                warn!("[{}] Command /{} is already registered", state.name, name);
+                return Ok(0);
+            }
+
+            let runtime = state.runtime.clone();
+            let command = Command::new(&name, &description, PermissionLevel::from_u8(permission.clamp(0, 4) as u8), move |_, sender, args| {
+                let Some(runtime) = runtime.upgrade() else {
+                    return Err(String::from("The plugin is no longer loaded"));
+                };
+                let data = json!({
+                    "sender": sender.get_name(),
+                    "args": args.get_string("args").unwrap_or_default(),
+                });
+                match lock(&runtime).call_with_data("on_command", id, &data.to_string()) {
+                    Some(0) => Ok(()),
+                    _ => Err(String::from("An error occurred while running the command")),
+                }
+            })
+                .overload(vec![CommandParameter::optional("args", ParameterType::Message)]);
+            state.commands.register(command);
+            state.registered_commands.push(name);
+            Ok(1)
+        },
+    )?;
+
+    linker.func_wrap(HOST_MODULE, "subscribe", |mut caller: Caller<'_, HostState>, kind: i32, priority: i32| {
+        let state = caller.data_mut();
+        let priority = match priority {
+            0 => EventPriority::Lowest,
+            1 => EventPriority::Low,
+            3 => EventPriority::High,
+            4 => EventPriority::Highest,
+            5 => EventPriority::Monitor,
+            _ => EventPriority::Normal,
+        };
+        let bus = state.context.event_bus.clone();
+        let runtime = state.runtime.clone();
+        let id = match kind {
+            EVENT_PLAYER_JOIN => bus.subscribe(priority, move |event: &mut PlayerJoinEvent| {
+                deliver(&runtime, kind, json!({ "player": event.player.display_name, "message": event.join_message }), event);
+            }),
+            EVENT_PLAYER_QUIT => bus.subscribe(priority, move |event: &mut PlayerQuitEvent| {
+                deliver(&runtime, kind, json!({ "player": event.player.display_name, "message": event.quit_message }), event);
+            }),
+            EVENT_PLAYER_CHAT => bus.subscribe(priority, move |event: &mut PlayerChatEvent| {
+                deliver(&runtime, kind, json!({ "player": event.player.display_name, "message": event.message }), event);
+            }),
+            EVENT_SERVER_TICK => bus.subscribe(priority, move |event: &mut ServerTickEvent| {
+                deliver(&runtime, kind, json!({ "tick": event.tick }), event);
+            }),
+            EVENT_PLAYER_PRE_LOGIN => bus.subscribe(priority, move |event: &mut PlayerPreLoginEvent| {
+                let address = event.address.map(|address| address.to_string());
+                deliver(&runtime, kind, json!({ "xuid": event.xuid, "player": event.display_name, "address": address }), event);
+            }),
+            EVENT_PACKET_RECEIVE => bus.subscribe(priority, move |event: &mut PacketReceiveEvent| {
+                try_deliver(&runtime, kind, json!({ "entity_id": event.entity_id, "packet": event.packet.get_name() }), event);
+            }),
+            EVENT_PACKET_SEND => bus.subscribe(priority, move |event: &mut PacketSendEvent| {
+                try_deliver(&runtime, kind, json!({ "entity_id": event.entity_id, "packet": event.packet.get_name() }), event);
+            }),
+            _ => {
+                warn!("[{}] Unknown event kind {}", state.name, kind);
+                return Ok(0);
+            }
+        };
+        state.listeners.push(id);
+        Ok(1)
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "schedule", |mut caller: Caller<'_, HostState>, delay: i64, period: i64, id: i32| {
+        let state = caller.data_mut();
+        state.next_task += 1;
+        let handle = state.next_task;
+        state.pending_tasks.push((handle, delay.max(0), period.max(0), id));
+        Ok(handle)
+    })?;
+
+    linker.func_wrap(HOST_MODULE, "cancel_task", |mut caller: Caller<'_, HostState>, handle: i32| {
+        let state = caller.data_mut();
+        let pending = state.pending_tasks.len();
+        state.pending_tasks.retain(|task| task.0 != handle);
+        if state.pending_tasks.len() < pending {
+            return Ok(1);
+        }
+        let cancelled = state.tasks.remove(&handle).inspect(TaskHandle::cancel).is_some();
+        Ok(cancelled as i32)
+    })?;
+
+    Ok(())
+}
+
+/// Passes an event to `on_event`, a non-zero result cancels it. Waits for the plugin, which
+/// pre-login also does on its session task since it runs once per login and may refuse it.
+fn deliver<E: Event>(runtime: &Weak<Mutex<WasmRuntime>>, kind: i32, data: serde_json::Value, event: &mut E) {
+    let Some(runtime) = runtime.upgrade() else {
+        return;
+    };
+    let result = lock(&runtime).call_with_data("on_event", kind, &data.to_string());
+    if result.is_some_and(|result| result != 0) {
+        event.set_cancelled(true);
+    }
+}
+
+/// `deliver` for events fired on the session tasks for every packet. Skips the event if
+/// the plugin is running a call elsewhere, which may take up to a full call's fuel.
+fn try_deliver<E: Event>(runtime: &Weak<Mutex<WasmRuntime>>, kind: i32, data: serde_json::Value, event: &mut E) {
+    let Some(runtime) = runtime.upgrade() else {
+        return;
+    };
+    let mut runtime = match runtime.try_lock() {
+        Ok(runtime) => runtime,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    let result = runtime.call_with_data("on_event", kind, &data.to_string());
+    if result.is_some_and(|result| result != 0) {
+        event.set_cancelled(true);
+    }
+}
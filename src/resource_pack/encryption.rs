@@ -0,0 +1,120 @@
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use aes::cipher::{AsyncStreamCipher, KeyIvInit};
+use aes::Aes256;
+use rand_core::{OsRng, RngCore};
+use serde_json::json;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+use crate::resource_pack::pack::ResourcePack;
+
+type Aes256Cfb8 = cfb8::Encryptor<Aes256>;
+
+pub const KEY_LENGTH: usize = 32;
+
+const CONTENTS_VERSION: u32 = 0;
+const CONTENTS_MAGIC: u32 = 0x9BCFB9FC;
+const CONTENTS_HEADER_LENGTH: usize = 0x100;
+
+// Files the client reads before the pack key is known stay in plain text.
+const UNENCRYPTED_FILES: &[&str] = &["manifest.json", "pack_icon.png", "bug_pack_icon.png"];
+
+/// Encrypts an unpacked pack folder into the Bedrock `contents.json` layout and writes
+/// it as a zip to `output`, together with a `<output>.key` sidecar. Every file gets its
+/// own key, `contents.json` lists them and is encrypted with the pack key.
+pub fn encrypt_pack(input: &Path, output: &Path, key: Option<String>) -> Result<String, String> {
+    let key = key.unwrap_or_else(generate_key);
+    if key.len() != KEY_LENGTH {
+        return Err(format!("Pack key must be {} characters long", KEY_LENGTH));
+    }
+
+    let pack = ResourcePack::load(input)?;
+    let files = list_files(input)?;
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut content = Vec::new();
+
+    for (name, path) in &files {
+        let mut data = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+        if UNENCRYPTED_FILES.contains(&name.as_str()) {
+            content.push(json!({ "path": name, "key": null }));
+        } else {
+            let file_key = generate_key();
+            encrypt(file_key.as_bytes(), &mut data);
+            content.push(json!({ "path": name, "key": file_key }));
+        }
+        write_entry(&mut writer, name, &data)?;
+    }
+
+    let mut contents = json!({ "content": content }).to_string().into_bytes();
+    encrypt(key.as_bytes(), &mut contents);
+
+    let content_id = pack.get_id().to_string();
+    let mut header = Vec::with_capacity(CONTENTS_HEADER_LENGTH + contents.len());
+    header.extend_from_slice(&CONTENTS_VERSION.to_le_bytes());
+    header.extend_from_slice(&CONTENTS_MAGIC.to_le_bytes());
+    header.extend_from_slice(&[0u8; 8]);
+    header.push(content_id.len() as u8);
+    header.extend_from_slice(content_id.as_bytes());
+    header.resize(CONTENTS_HEADER_LENGTH, 0);
+    header.extend_from_slice(&contents);
+    write_entry(&mut writer, "contents.json", &header)?;
+
+    let cursor = writer.finish().map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+    fs::write(output, cursor.into_inner()).map_err(|e| format!("Failed to write {:?}: {}", output, e))?;
+    fs::write(key_path(output), &key).map_err(|e| format!("Failed to write key for {:?}: {}", output, e))?;
+
+    Ok(key)
+}
+
+/// Path of the sidecar file holding the content key of the pack at `path`.
+pub fn key_path(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".key");
+    PathBuf::from(path)
+}
+
+fn encrypt(key: &[u8], data: &mut [u8]) {
+    let iv = &key[..16];
+    Aes256Cfb8::new(key.into(), iv.into()).encrypt(data);
+}
+
+fn generate_key() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+    let mut bytes = [0u8; KEY_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| CHARSET[*byte as usize % CHARSET.len()] as char).collect()
+}
+
+fn write_entry(writer: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, data: &[u8]) -> Result<(), String> {
+    writer.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| format!("Failed to zip {}: {}", name, e))?;
+    writer.write_all(data).map_err(|e| format!("Failed to zip {}: {}", name, e))
+}
+
+fn list_files(root: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let mut files = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory).map_err(|e| format!("Failed to read {:?}: {}", directory, e))? {
+            let path = entry.map_err(|e| format!("Failed to read {:?}: {}", directory, e))?.path();
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            let name = path.strip_prefix(root)
+                .map_err(|e| format!("Invalid pack path {:?}: {}", path, e))?
+                .to_string_lossy()
+                .replace('\\', "/");
+            if name != "contents.json" {
+                files.push((name, path));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
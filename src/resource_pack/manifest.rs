@@ -0,0 +1,81 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackManifest {
+    pub format_version: u32,
+    pub header: ManifestHeader,
+    #[serde(default)]
+    pub modules: Vec<ManifestModule>,
+    #[serde(default)]
+    pub dependencies: Vec<ManifestDependency>,
+}
+
+impl PackManifest {
+    pub fn is_behavior_pack(&self) -> bool {
+        self.modules.iter().any(|module| matches!(module.module_type.as_str(), "data" | "script" | "javascript"))
+    }
+
+    pub fn has_scripts(&self) -> bool {
+        self.modules.iter().any(|module| matches!(module.module_type.as_str(), "script" | "javascript"))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestHeader {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub uuid: Uuid,
+    pub version: PackVersion,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestModule {
+    #[serde(rename = "type")]
+    pub module_type: String,
+    pub uuid: Uuid,
+    pub version: PackVersion,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestDependency {
+    pub uuid: Option<Uuid>,
+    pub module_name: Option<String>,
+    pub version: PackVersion,
+}
+
+/// Pack versions are `[major, minor, patch]` arrays in older manifests and semver
+/// strings since format version 3.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(try_from = "serde_json::Value")]
+pub struct PackVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl TryFrom<serde_json::Value> for PackVersion {
+    type Error = String;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        let parts: Vec<u32> = match &value {
+            serde_json::Value::Array(parts) => parts.iter().filter_map(|part| part.as_u64().map(|v| v as u32)).collect(),
+            serde_json::Value::String(version) => version.split(['.', '-'])
+                .take(3)
+                .map(|part| part.parse().map_err(|_| format!("Invalid pack version {}", version)))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(format!("Invalid pack version {}", value)),
+        };
+        if parts.len() != 3 {
+            return Err(format!("Invalid pack version {}", value));
+        }
+        Ok(PackVersion { major: parts[0], minor: parts[1], patch: parts[2] })
+    }
+}
+
+impl std::fmt::Display for PackVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
@@ -1 +1,4 @@
-// pub mod zipped_resource_pack;
\ No newline at end of file
+pub mod encryption;
+pub mod manifest;
+pub mod pack;
+pub mod resource_pack_manager;
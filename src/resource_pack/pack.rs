@@ -0,0 +1,280 @@
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::resource_pack::encryption::key_path;
+use crate::resource_pack::manifest::{PackManifest, PackVersion};
+
+pub struct ResourcePack {
+    pub manifest: PackManifest,
+    pub path: PathBuf,
+    pub sha256: Vec<u8>,
+    pub content_key: String,
+    data: Vec<u8>,
+}
+
+impl ResourcePack {
+    /// Loads a zipped pack (`.zip`/`.mcpack`) or an unpacked pack folder. Folders are
+    /// zipped in memory so both are served to clients the same way.
+    pub fn load(path: &Path) -> Result<ResourcePack, String> {
+        let data = if path.is_dir() {
+            zip_directory(path)?
+        } else {
+            fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?
+        };
+        let mut pack = ResourcePack::from_bytes(path, data)?;
+
+        let key_path = key_path(path);
+        if key_path.is_file() {
+            pack.content_key = fs::read_to_string(&key_path)
+                .map_err(|e| format!("Failed to read {:?}: {}", key_path, e))?
+                .trim()
+                .to_string();
+        }
+        Ok(pack)
+    }
+
+    pub fn from_bytes(path: &Path, data: Vec<u8>) -> Result<ResourcePack, String> {
+        let manifest = read_manifest(&data).map_err(|e| format!("{:?}: {}", path, e))?;
+        let sha256 = Sha256::digest(&data).to_vec();
+
+        Ok(ResourcePack {
+            manifest,
+            path: path.to_path_buf(),
+            sha256,
+            content_key: String::new(),
+            data,
+        })
+    }
+
+    pub fn get_id(&self) -> Uuid {
+        self.manifest.header.uuid
+    }
+
+    pub fn get_version(&self) -> &PackVersion {
+        &self.manifest.header.version
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.manifest.header.name
+    }
+
+    pub fn is_behavior_pack(&self) -> bool {
+        self.manifest.is_behavior_pack()
+    }
+
+    pub fn has_scripts(&self) -> bool {
+        self.manifest.has_scripts()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        !self.content_key.is_empty()
+    }
+
+    /// The `<uuid>_<version>` identifier clients use in pack responses and chunk requests.
+    pub fn get_pack_id(&self) -> String {
+        format!("{}_{}", self.get_id(), self.get_version())
+    }
+
+    pub fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn get_pack_size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    pub fn get_chunk_count(&self, chunk_size: u32) -> u32 {
+        self.get_pack_size().div_ceil(chunk_size as u64) as u32
+    }
+
+    /// Returns the byte offset and data of chunk `index`, or `None` when the index is
+    /// past the end of the pack.
+    pub fn get_chunk(&self, chunk_size: u32, index: u32) -> Option<(u64, Vec<u8>)> {
+        if index >= self.get_chunk_count(chunk_size) {
+            return None;
+        }
+        let offset = chunk_size as u64 * index as u64;
+        let len = (self.get_pack_size() - offset).min(chunk_size as u64);
+        self.get_pack_chunk(offset, len as usize).map(|data| (offset, data))
+    }
+
+    pub fn get_pack_chunk(&self, offset: u64, len: usize) -> Option<Vec<u8>> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(len)?;
+        self.data.get(start..end).map(|chunk| chunk.to_vec())
+    }
+}
+
+fn read_manifest(data: &[u8]) -> Result<PackManifest, String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| format!("Invalid pack archive: {}", e))?;
+
+    // The manifest is either at the root or inside the single top-level folder.
+    let name = archive.file_names()
+        .filter(|name| name.rsplit('/').next() == Some("manifest.json"))
+        .min_by_key(|name| name.matches('/').count())
+        .map(|name| name.to_string())
+        .ok_or("Missing manifest.json")?;
+
+    let mut content = String::new();
+    archive.by_name(&name)
+        .map_err(|e| format!("Failed to open {}: {}", name, e))?
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Invalid manifest.json: {}", e))
+}
+
+fn zip_directory(root: &Path) -> Result<Vec<u8>, String> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(&directory)
+            .map_err(|e| format!("Failed to read {:?}: {}", directory, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            if path.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            let name = path.strip_prefix(root)
+                .map_err(|e| format!("Invalid pack path {:?}: {}", path, e))?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let content = fs::read(&path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+            writer.start_file(name, SimpleFileOptions::default())
+                .map_err(|e| format!("Failed to zip {:?}: {}", path, e))?;
+            writer.write_all(&content)
+                .map_err(|e| format!("Failed to zip {:?}: {}", path, e))?;
+        }
+    }
+
+    let cursor = writer.finish().map_err(|e| format!("Failed to zip {:?}: {}", root, e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Splits an `.mcaddon` bundle into its packs. Bundles contain either nested
+/// `.mcpack`/`.zip` archives or one folder per pack, each with its own manifest.
+pub fn split_addon(path: &Path) -> Result<Vec<ResourcePack>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| format!("{:?}: Invalid addon archive: {}", path, e))?;
+    let names: Vec<String> = archive.file_names().map(|name| name.to_string()).collect();
+    let mut packs = Vec::new();
+
+    for name in names.iter().filter(|name| name.ends_with(".mcpack") || name.ends_with(".zip")) {
+        let content = read_entry(&mut archive, name)?;
+        packs.push(ResourcePack::from_bytes(&path.join(name), content)?);
+    }
+
+    let manifest_roots: Vec<&str> = names.iter()
+        .filter_map(|name| name.strip_suffix("manifest.json"))
+        .filter(|root| root.is_empty() || root.ends_with('/'))
+        .collect();
+    // Manifests nested inside another pack (sub packs) belong to that pack.
+    let pack_roots = manifest_roots.iter()
+        .filter(|root| !manifest_roots.iter().any(|other| other.len() < root.len() && root.starts_with(*other)));
+    for root in pack_roots {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for name in names.iter().filter(|name| name.starts_with(root) && !name.ends_with('/')) {
+            let content = read_entry(&mut archive, name)?;
+            writer.start_file(&name[root.len()..], SimpleFileOptions::default())
+                .map_err(|e| format!("Failed to zip {}: {}", name, e))?;
+            writer.write_all(&content)
+                .map_err(|e| format!("Failed to zip {}: {}", name, e))?;
+        }
+        let cursor = writer.finish().map_err(|e| format!("Failed to zip {}: {}", root, e))?;
+        packs.push(ResourcePack::from_bytes(&path.join(root), cursor.into_inner())?);
+    }
+
+    Ok(packs)
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<Vec<u8>, String> {
+    let mut content = Vec::new();
+    archive.by_name(name)
+        .map_err(|e| format!("Failed to open {}: {}", name, e))?
+        .read_to_end(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(content)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const PACK_UUID: &str = "5f2a5c1e-3b6a-4d1f-9a57-0c6d2b8e4f10";
+
+    /// A zipped pack with a manifest and an incompressible file of `size` bytes, so the
+    /// archive spans several chunks.
+    pub(crate) fn pack_bytes(size: usize) -> Vec<u8> {
+        let manifest = format!(
+            r#"{{"format_version": 2, "header": {{"name": "Test", "uuid": "{}", "version": [1, 0, 0]}}}}"#,
+            PACK_UUID,
+        );
+        let mut state: u32 = 0x2545f491;
+        let noise: Vec<u8> = (0..size).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        }).collect();
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("manifest.json", SimpleFileOptions::default()).unwrap();
+        writer.write_all(manifest.as_bytes()).unwrap();
+        writer.start_file("textures/noise.bin", SimpleFileOptions::default()).unwrap();
+        writer.write_all(&noise).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn rebuild(pack: &ResourcePack, chunk_size: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        for index in 0..pack.get_chunk_count(chunk_size) {
+            let (offset, chunk) = pack.get_chunk(chunk_size, index).unwrap();
+            assert_eq!(offset, data.len() as u64);
+            data.extend_from_slice(&chunk);
+        }
+        data
+    }
+
+    #[test]
+    fn rebuilt_pack_matches_hash() {
+        let pack = ResourcePack::from_bytes(Path::new("test.mcpack"), pack_bytes(10_000)).unwrap();
+        for chunk_size in [1024, 4096, 1024 * 1024] {
+            let data = rebuild(&pack, chunk_size);
+            assert_eq!(data.len() as u64, pack.get_pack_size());
+            assert_eq!(Sha256::digest(&data).to_vec(), pack.sha256);
+        }
+    }
+
+    #[test]
+    fn last_chunk_is_partial() {
+        let pack = ResourcePack::from_bytes(Path::new("test.mcpack"), pack_bytes(10_000)).unwrap();
+        let chunk_size = 1024;
+        let count = pack.get_chunk_count(chunk_size);
+        let remainder = pack.get_pack_size() % chunk_size as u64;
+        assert_ne!(remainder, 0, "the test pack should not be a multiple of the chunk size");
+
+        let (offset, chunk) = pack.get_chunk(chunk_size, count - 1).unwrap();
+        assert_eq!(chunk.len() as u64, remainder);
+        assert_eq!(offset + chunk.len() as u64, pack.get_pack_size());
+    }
+
+    #[test]
+    fn rejects_out_of_range_chunks() {
+        let pack = ResourcePack::from_bytes(Path::new("test.mcpack"), pack_bytes(10_000)).unwrap();
+        let count = pack.get_chunk_count(1024);
+        assert!(pack.get_chunk(1024, count).is_none());
+        assert!(pack.get_chunk(1024, u32::MAX).is_none());
+        assert!(pack.get_pack_chunk(pack.get_pack_size(), 1).is_none());
+        assert!(pack.get_pack_chunk(u64::MAX, 1).is_none());
+    }
+}
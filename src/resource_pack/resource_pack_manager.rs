@@ -0,0 +1,235 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use log::{error, info, warn};
+use uuid::Uuid;
+use crate::resource_pack::manifest::PackVersion;
+use crate::resource_pack::pack::{split_addon, ResourcePack};
+
+pub const DEFAULT_CHUNK_SIZE: u32 = 1024 * 100;
+
+pub struct ResourcePackManager {
+    resource_packs: Vec<Arc<ResourcePack>>,
+    behavior_packs: Vec<Arc<ResourcePack>>,
+    index: HashMap<(Uuid, PackVersion), Arc<ResourcePack>>,
+    content_keys: HashMap<Uuid, String>,
+    chunk_size: u32,
+}
+
+impl Default for ResourcePackManager {
+    fn default() -> Self {
+        Self {
+            resource_packs: Vec::new(),
+            behavior_packs: Vec::new(),
+            index: HashMap::new(),
+            content_keys: HashMap::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+}
+
+impl ResourcePackManager {
+    /// Loads every `.zip`/`.mcpack`/`.mcaddon` file and pack folder in the resource and
+    /// behavior pack directories once. Packs are sorted by the type their manifest declares,
+    /// broken packs are logged and skipped. Encrypted packs take their key from a
+    /// `<pack>.key` sidecar, or from `content_keys` by pack UUID.
+    pub fn load(
+        resource_pack_path: &Path,
+        behavior_pack_path: &Path,
+        content_keys: HashMap<Uuid, String>,
+        chunk_size: u32,
+    ) -> ResourcePackManager {
+        let mut manager = ResourcePackManager {
+            content_keys,
+            chunk_size,
+            ..ResourcePackManager::default()
+        };
+
+        for entry in list_directory(resource_pack_path).into_iter().chain(list_directory(behavior_pack_path)) {
+            let extension = entry.extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_lowercase());
+
+            match extension.as_deref() {
+                Some("mcaddon") => match split_addon(&entry) {
+                    Ok(packs) => packs.into_iter().for_each(|pack| manager.register(pack)),
+                    Err(err) => error!("Failed to load addon {}", err),
+                },
+                Some("zip") | Some("mcpack") => manager.load_pack(&entry),
+                _ if entry.is_dir() => manager.load_pack(&entry),
+                _ => {}
+            }
+        }
+
+        manager.resource_packs = sort_by_dependencies(&manager.resource_packs);
+        manager.behavior_packs = sort_by_dependencies(&manager.behavior_packs);
+        manager.report_missing_dependencies();
+
+        info!(
+            "Loaded {} resource pack(s) and {} behavior pack(s)",
+            manager.resource_packs.len(),
+            manager.behavior_packs.len(),
+        );
+        manager
+    }
+
+    fn load_pack(&mut self, path: &Path) {
+        match ResourcePack::load(path) {
+            Ok(pack) => self.register(pack),
+            Err(err) => error!("Failed to load pack {}", err),
+        }
+    }
+
+    fn register(&mut self, mut pack: ResourcePack) {
+        if !pack.is_encrypted() {
+            if let Some(content_key) = self.content_keys.get(&pack.get_id()) {
+                pack.content_key = content_key.clone();
+            }
+        }
+
+        let key = (pack.get_id(), pack.get_version().clone());
+        if self.index.contains_key(&key) {
+            warn!("Skipping duplicate pack {} ({:?})", pack.get_pack_id(), pack.path);
+            return;
+        }
+
+        let kind = if pack.is_behavior_pack() { "behavior" } else { "resource" };
+        info!("Loaded {} pack {} v{}", kind, pack.get_name(), pack.get_version());
+        let pack = Arc::new(pack);
+        self.index.insert(key, pack.clone());
+        if pack.is_behavior_pack() {
+            self.behavior_packs.push(pack);
+        } else {
+            self.resource_packs.push(pack);
+        }
+    }
+
+    /// Dependencies must be loaded at the exact version the manifest asks for.
+    fn report_missing_dependencies(&self) {
+        for pack in self.get_packs() {
+            for dependency in &pack.manifest.dependencies {
+                let Some(uuid) = dependency.uuid else {
+                    continue;
+                };
+                if self.get_pack(&uuid, &dependency.version).is_some() {
+                    continue;
+                }
+                let loaded: Vec<String> = self.get_packs()
+                    .filter(|loaded| loaded.get_id() == uuid)
+                    .map(|loaded| format!("v{}", loaded.get_version()))
+                    .collect();
+                if loaded.is_empty() {
+                    warn!(
+                        "Pack {} v{} depends on missing pack {} v{}",
+                        pack.get_name(), pack.get_version(), uuid, dependency.version,
+                    );
+                } else {
+                    warn!(
+                        "Pack {} v{} depends on pack {} v{}, but only {} is loaded",
+                        pack.get_name(), pack.get_version(), uuid, dependency.version, loaded.join(", "),
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn get_chunk_size(&self) -> u32 {
+        self.chunk_size
+    }
+
+    pub fn get_resource_packs(&self) -> &[Arc<ResourcePack>] {
+        &self.resource_packs
+    }
+
+    pub fn get_behavior_packs(&self) -> &[Arc<ResourcePack>] {
+        &self.behavior_packs
+    }
+
+    pub fn get_packs(&self) -> impl Iterator<Item = &Arc<ResourcePack>> {
+        self.resource_packs.iter().chain(self.behavior_packs.iter())
+    }
+
+    pub fn get_pack(&self, uuid: &Uuid, version: &PackVersion) -> Option<Arc<ResourcePack>> {
+        self.index.get(&(*uuid, version.clone())).cloned()
+    }
+
+    /// Looks up a pack by the `<uuid>_<version>` identifier sent by clients. A bare UUID
+    /// resolves to the first loaded version of that pack.
+    pub fn get_pack_by_id(&self, pack_id: &str) -> Option<Arc<ResourcePack>> {
+        let (uuid, version) = match pack_id.split_once('_') {
+            Some((uuid, version)) => (uuid, Some(version)),
+            None => (pack_id, None),
+        };
+        let uuid = Uuid::parse_str(uuid).ok()?;
+
+        match version {
+            Some(version) => {
+                let version = PackVersion::try_from(serde_json::Value::String(version.to_string())).ok()?;
+                self.get_pack(&uuid, &version)
+            }
+            None => self.get_packs().find(|pack| pack.get_id() == uuid).cloned(),
+        }
+    }
+}
+
+fn list_directory(path: &Path) -> Vec<PathBuf> {
+    if !path.exists() {
+        if let Err(err) = fs::create_dir_all(path) {
+            error!("Failed to create directory {:?}: {}", path, err);
+        }
+        return Vec::new();
+    }
+
+    match fs::read_dir(path) {
+        Ok(entries) => {
+            let mut entries: Vec<PathBuf> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+            entries.sort();
+            entries
+        }
+        Err(err) => {
+            error!("Failed to read {:?}: {}", path, err);
+            Vec::new()
+        }
+    }
+}
+
+/// Orders packs for the pack stack, where earlier entries take priority: every pack is
+/// placed after the packs that depend on it so they can override it. Unrelated packs and
+/// packs in a dependency cycle keep their load order.
+fn sort_by_dependencies(packs: &[Arc<ResourcePack>]) -> Vec<Arc<ResourcePack>> {
+    let mut sorted: Vec<Arc<ResourcePack>> = Vec::with_capacity(packs.len());
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut in_progress: HashSet<String> = HashSet::new();
+
+    fn visit(
+        pack: &Arc<ResourcePack>,
+        packs: &[Arc<ResourcePack>],
+        sorted: &mut Vec<Arc<ResourcePack>>,
+        visited: &mut HashSet<String>,
+        in_progress: &mut HashSet<String>,
+    ) {
+        if visited.contains(&pack.get_pack_id()) {
+            return;
+        }
+        if !in_progress.insert(pack.get_pack_id()) {
+            warn!("Dependency cycle detected at pack {}", pack.get_pack_id());
+            return;
+        }
+        let dependents = packs.iter().filter(|other| {
+            other.manifest.dependencies.iter().any(|dependency| dependency.uuid == Some(pack.get_id()))
+        });
+        for dependent in dependents {
+            visit(dependent, packs, sorted, visited, in_progress);
+        }
+        in_progress.remove(&pack.get_pack_id());
+        if visited.insert(pack.get_pack_id()) {
+            sorted.push(pack.clone());
+        }
+    }
+
+    for pack in packs {
+        visit(pack, packs, &mut sorted, &mut visited, &mut in_progress);
+    }
+    sorted
+}
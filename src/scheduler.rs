@@ -0,0 +1,259 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::task::AbortHandle;
+use crate::server::Server;
+
+pub type Task = Box<dyn FnMut(&mut Server) + Send>;
+
+/// Returned for every scheduled task. Clones share the same task, cancelling any of them
+/// stops it before its next run.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+    abort: Option<AbortHandle>,
+}
+
+impl TaskHandle {
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+pub(crate) struct ScheduledTask {
+    id: u64,
+    task: Task,
+    period: Option<i64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScheduledTask {
+    pub(crate) fn run(&mut self, server: &mut Server) {
+        if !self.cancelled.load(Ordering::Relaxed) {
+            (self.task)(server);
+        }
+    }
+}
+
+/// Runs tasks on the main tick. Tasks due on the same tick run in the order they were
+/// scheduled, repeating tasks keep their place between runs.
+#[derive(Default)]
+pub struct Scheduler {
+    current_tick: i64,
+    next_id: u64,
+    tasks: BTreeMap<(i64, u64), ScheduledTask>,
+}
+
+impl Scheduler {
+    /// Runs `task` once, `delay_ticks` ticks from now. A delay of 0 runs it on the next tick.
+    pub fn run_later<F>(&mut self, delay_ticks: i64, task: F) -> TaskHandle
+    where
+        F: FnMut(&mut Server) + Send + 'static,
+    {
+        self.schedule(delay_ticks, None, Box::new(task))
+    }
+
+    /// Runs `task` after `delay_ticks` and then every `period_ticks` until cancelled.
+    pub fn run_repeating<F>(&mut self, delay_ticks: i64, period_ticks: i64, task: F) -> TaskHandle
+    where
+        F: FnMut(&mut Server) + Send + 'static,
+    {
+        self.schedule(delay_ticks, Some(period_ticks.max(1)), Box::new(task))
+    }
+
+    /// Runs `future` on the tokio runtime, off the main tick. Cancelling aborts it.
+    pub fn run_async<F>(&mut self, future: F) -> TaskHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let join_handle = tokio::spawn(future);
+        TaskHandle {
+            id: self.next_id(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+            abort: Some(join_handle.abort_handle()),
+        }
+    }
+
+    pub fn get_pending_count(&self) -> usize {
+        self.tasks.values()
+            .filter(|task| !task.cancelled.load(Ordering::Relaxed))
+            .count()
+    }
+
+    pub fn cancel_all(&mut self) {
+        for task in self.tasks.values() {
+            task.cancelled.store(true, Ordering::Relaxed);
+        }
+        self.tasks.clear();
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn schedule(&mut self, delay_ticks: i64, period: Option<i64>, task: Task) -> TaskHandle {
+        let id = self.next_id();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let due = self.current_tick + delay_ticks.max(1);
+        self.tasks.insert((due, id), ScheduledTask { id, task, period, cancelled: cancelled.clone() });
+        TaskHandle { id, cancelled, abort: None }
+    }
+
+    /// Moves to `tick`. Called at the start of every tick, before anything that could
+    /// schedule tasks runs, so delays count from the tick they were scheduled in.
+    pub(crate) fn advance(&mut self, tick: i64) {
+        self.current_tick = tick;
+    }
+
+    /// Removes the tasks due at the current tick, in the order they have to run.
+    pub(crate) fn take_due(&mut self) -> Vec<ScheduledTask> {
+        let pending = self.tasks.split_off(&(self.current_tick + 1, 0));
+        std::mem::replace(&mut self.tasks, pending)
+            .into_values()
+            .filter(|task| !task.cancelled.load(Ordering::Relaxed))
+            .collect()
+    }
+
+    /// Puts a task that just ran back in the queue if it repeats and was not cancelled.
+    pub(crate) fn reschedule(&mut self, task: ScheduledTask) {
+        if let Some(period) = task.period {
+            if !task.cancelled.load(Ordering::Relaxed) {
+                self.tasks.insert((self.current_tick + period, task.id), task);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    type Log = Arc<Mutex<Vec<(i64, &'static str)>>>;
+
+    /// Drives `scheduler` the way `Server::tick` does.
+    struct Ticker {
+        scheduler: Scheduler,
+        server: Server,
+        tick: Arc<Mutex<i64>>,
+        log: Log,
+    }
+
+    impl Ticker {
+        fn new() -> Ticker {
+            Ticker {
+                scheduler: Scheduler::default(),
+                server: Server::default(),
+                tick: Arc::new(Mutex::new(0)),
+                log: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        /// A task that records the tick it ran on.
+        fn task(&self, name: &'static str) -> impl FnMut(&mut Server) + Send + 'static {
+            let (log, tick) = (self.log.clone(), self.tick.clone());
+            move |_| log.lock().unwrap().push((*tick.lock().unwrap(), name))
+        }
+
+        fn run_until(&mut self, tick: i64) -> Vec<(i64, &'static str)> {
+            while *self.tick.lock().unwrap() < tick {
+                let current = {
+                    let mut current = self.tick.lock().unwrap();
+                    *current += 1;
+                    *current
+                };
+                self.scheduler.advance(current);
+                for mut task in self.scheduler.take_due() {
+                    task.run(&mut self.server);
+                    self.scheduler.reschedule(task);
+                }
+            }
+            std::mem::take(&mut *self.log.lock().unwrap())
+        }
+    }
+
+    #[test]
+    fn tasks_due_on_the_same_tick_run_in_scheduling_order() {
+        let mut ticker = Ticker::new();
+        ticker.scheduler.run_later(2, ticker.task("a"));
+        ticker.scheduler.run_later(1, ticker.task("b"));
+        ticker.scheduler.run_repeating(1, 1, ticker.task("repeating"));
+        ticker.scheduler.run_later(2, ticker.task("c"));
+        ticker.scheduler.run_later(0, ticker.task("next tick"));
+
+        assert_eq!(ticker.run_until(3), vec![
+            (1, "b"),
+            (1, "repeating"),
+            (1, "next tick"),
+            (2, "a"),
+            (2, "repeating"),
+            (2, "c"),
+            (3, "repeating"),
+        ]);
+    }
+
+    #[test]
+    fn repeating_task_keeps_its_place() {
+        let mut ticker = Ticker::new();
+        ticker.scheduler.run_repeating(1, 2, ticker.task("repeating"));
+        ticker.scheduler.run_later(3, ticker.task("later"));
+
+        assert_eq!(ticker.run_until(5), vec![
+            (1, "repeating"),
+            (3, "repeating"),
+            (3, "later"),
+            (5, "repeating"),
+        ]);
+    }
+
+    #[test]
+    fn task_cancelled_by_an_earlier_task_on_the_same_tick_does_not_run() {
+        let mut ticker = Ticker::new();
+        let victim: Arc<Mutex<Option<TaskHandle>>> = Arc::new(Mutex::new(None));
+        let mut record_cancel = ticker.task("canceller");
+        let target = victim.clone();
+        ticker.scheduler.run_later(1, move |server: &mut Server| {
+            record_cancel(server);
+            if let Some(handle) = target.lock().unwrap().as_ref() {
+                handle.cancel();
+            }
+        });
+        let handle = ticker.scheduler.run_repeating(1, 1, ticker.task("victim"));
+        *victim.lock().unwrap() = Some(handle.clone());
+
+        assert_eq!(ticker.run_until(3), vec![(1, "canceller")]);
+        assert!(handle.is_cancelled());
+        assert_eq!(ticker.scheduler.get_pending_count(), 0);
+    }
+
+    #[test]
+    fn repeating_task_can_cancel_itself() {
+        let mut ticker = Ticker::new();
+        let slot: Arc<Mutex<Option<TaskHandle>>> = Arc::new(Mutex::new(None));
+        let own = slot.clone();
+        let mut record_run = ticker.task("repeating");
+        let handle = ticker.scheduler.run_repeating(1, 1, move |server: &mut Server| {
+            record_run(server);
+            if let Some(handle) = own.lock().unwrap().as_ref() {
+                handle.cancel();
+            }
+        });
+        *slot.lock().unwrap() = Some(handle);
+
+        assert_eq!(ticker.run_until(3), vec![(1, "repeating")]);
+    }
+}
@@ -1,49 +1,167 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::path::Path;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use bedrockrs::proto::listener::Listener;
-use chrono::Utc;
-use log::{error, info};
+use log::{error, info, warn};
 use once_cell::sync::Lazy;
 use tokio::fs;
-use tokio::sync::{Mutex, OnceCell, RwLock};
-use tokio::time::{sleep, Instant};
+use tokio::sync::{mpsc, watch, Mutex, OnceCell, RwLock};
+use tokio::time::{sleep, sleep_until, Instant};
 use tokio::time::Duration;
+use chrono::Utc;
+use serde_yaml::Value;
+use uuid::Uuid;
+use bedrockrs::proto::v662::enums::{Gamemode, TextPacketType};
+use crate::access::AccessControl;
+use crate::command::{self, available_commands, PermissionLevel};
+use crate::command::registry::CommandRegistry;
+use crate::command::sender::CommandSender;
+use crate::console::{self, Console};
+use crate::event::{Event, EventBus};
+use crate::event::player::{PlayerChatEvent, PlayerJoinEvent, PlayerQuitEvent};
+use crate::event::server::ServerTickEvent;
+use crate::level::Level;
+use crate::network::advertisement::Advertisement;
 use crate::network::Network;
+use crate::network::protocol::packet::Packet;
+use crate::network::session_registry::{SessionEvent, SessionHandle, SessionRegistry};
+use crate::permission::{abilities, PermissionManager};
+use crate::plugin::PluginManager;
+use crate::resource_pack::resource_pack_manager::ResourcePackManager;
+use crate::scheduler::Scheduler;
+use crate::server_properties::ServerProperties;
+use crate::utils::config::{Config, ConfigType};
 use crate::utils::rolling_float_average::RollingFloatAverage;
 
+/// Handles to the shared parts of the server, cloned into every network task so sessions
+/// never need to lock the `Server` itself.
+#[derive(Clone)]
+pub struct ServerContext {
+    pub properties: Arc<ServerProperties>,
+    pub advertisement: Arc<Advertisement>,
+    pub resource_pack_manager: Arc<ResourcePackManager>,
+    pub level: Arc<Level>,
+    pub permissions: Arc<PermissionManager>,
+    pub access: Arc<AccessControl>,
+    pub event_bus: Arc<EventBus>,
+    pub sessions: Arc<SessionRegistry>,
+    pub events: mpsc::UnboundedSender<SessionEvent>,
+    pub shutdown: Arc<watch::Sender<Option<String>>>,
+}
+
+impl ServerContext {
+    /// Asks the main tick to shut the server down with `reason`.
+    pub fn request_shutdown(&self, reason: &str) {
+        self.shutdown.send_replace(Some(reason.to_string()));
+    }
+
+    /// Resolves once a shutdown was requested.
+    pub fn subscribe_shutdown(&self) -> watch::Receiver<Option<String>> {
+        self.shutdown.subscribe()
+    }
+}
+
+const TICKS_PER_SECOND: f64 = 20.0;
+const TICK_INTERVAL: Duration = Duration::from_millis(50);
+const LAG_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Windows the TPS history is kept for, sampled once per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickWindow {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+}
+
+impl TickWindow {
+    fn index(self) -> usize {
+        match self {
+            TickWindow::OneMinute => 0,
+            TickWindow::FiveMinutes => 1,
+            TickWindow::FifteenMinutes => 2,
+        }
+    }
+}
+
 pub struct Server {
     lunchtime: u128,
     data_path: String,
+    properties: Arc<ServerProperties>,
+    advertisement: Arc<Advertisement>,
+    resource_pack_manager: Arc<ResourcePackManager>,
+    level: Arc<Level>,
+    permissions: Arc<PermissionManager>,
+    access: Arc<AccessControl>,
+    event_bus: Arc<EventBus>,
+    sessions: Arc<SessionRegistry>,
+    session_events: mpsc::UnboundedReceiver<SessionEvent>,
+    session_event_sender: mpsc::UnboundedSender<SessionEvent>,
+    scheduler: Scheduler,
+    commands: Arc<CommandRegistry>,
+    plugins: PluginManager,
+    console: Option<Console>,
+    shutdown_sender: Arc<watch::Sender<Option<String>>>,
+    shutdown_requests: watch::Receiver<Option<String>>,
 
     is_running: bool,
 
     tick: i64,
-    next_tick_ms: i64,
+    next_tick: Instant,
+    last_tick_start: Option<Instant>,
+    last_lag_warning: Option<Instant>,
 
-    tick_min: f64,
-    usage_max: f64,
+    tps: f64,
+    tick_usage: f64,
 
     tick_avg: RollingFloatAverage,
     usage_avg: RollingFloatAverage,
+    tps_history: [RollingFloatAverage; 3],
+    usage_history: [RollingFloatAverage; 3],
 }
 
 impl Default for Server {
     fn default() -> Self {
+        let (session_event_sender, session_events) = mpsc::unbounded_channel();
+        let (shutdown_sender, shutdown_requests) = watch::channel(None);
+        let commands = CommandRegistry::default();
+        command::defaults::register(&commands);
+        command::access::register(&commands);
         Self {
             lunchtime: 0,
             data_path: "".to_string(),
+            properties: Arc::new(ServerProperties::default()),
+            advertisement: Arc::new(Advertisement::new(&ServerProperties::default())),
+            resource_pack_manager: Arc::new(ResourcePackManager::default()),
+            level: Arc::new(Level::new(&ServerProperties::default())),
+            permissions: Arc::new(PermissionManager::default()),
+            access: Arc::new(AccessControl::default()),
+            event_bus: Arc::new(EventBus::default()),
+            sessions: Arc::new(SessionRegistry::default()),
+            session_events,
+            session_event_sender,
+            scheduler: Scheduler::default(),
+            commands: Arc::new(commands),
+            plugins: PluginManager::default(),
+            console: None,
+            shutdown_sender: Arc::new(shutdown_sender),
+            shutdown_requests,
             is_running: true,
 
             tick: 0,
-            next_tick_ms: Utc::now().timestamp_millis(),
+            next_tick: Instant::now(),
+            last_tick_start: None,
+            last_lag_warning: None,
 
-            tick_min: 20.0,
-            usage_max: 0.0,
+            tps: TICKS_PER_SECOND,
+            tick_usage: 0.0,
 
             tick_avg: RollingFloatAverage::new(20),
             usage_avg: RollingFloatAverage::new(20),
+            tps_history: [RollingFloatAverage::new(60), RollingFloatAverage::new(5 * 60), RollingFloatAverage::new(15 * 60)],
+            usage_history: [RollingFloatAverage::new(60), RollingFloatAverage::new(5 * 60), RollingFloatAverage::new(15 * 60)],
         }
     }
 }
@@ -68,79 +186,426 @@ impl Server {
 
         // let data_path = fs::canonicalize(data_path);
 
-        Server::default()
+        let properties_path = format!("{}/server.properties", data_path);
+        let properties = ServerProperties::load(&properties_path).unwrap_or_else(|err| {
+            error!("Failed to load {}: {}, using defaults", properties_path, err);
+            ServerProperties::default()
+        });
+
+        let resource_pack_manager = ResourcePackManager::load(
+            Path::new(&format!("{}/resource_packs/", data_path)),
+            Path::new(&format!("{}/behavior_packs/", data_path)),
+            load_content_keys(&format!("{}/resource_pack_keys.yml", data_path)),
+            properties.resource_pack_chunk_size,
+        );
+
+        let mut server = Server {
+            lunchtime,
+            data_path: data_path.to_string(),
+            advertisement: Arc::new(Advertisement::new(&properties)),
+            resource_pack_manager: Arc::new(resource_pack_manager),
+            level: Arc::new(Level::new(&properties)),
+            permissions: Arc::new(PermissionManager::load(data_path)),
+            access: Arc::new(AccessControl::load(data_path, properties.white_list)),
+            properties: Arc::new(properties),
+            ..Server::default()
+        };
+        server.plugins = PluginManager::load(
+            Path::new(&format!("{}/plugins/", data_path)),
+            &server.get_context(),
+            server.get_commands(),
+        );
+        server
+    }
+
+    pub fn get_properties(&self) -> &ServerProperties {
+        &self.properties
+    }
+
+    pub fn get_advertisement(&self) -> Arc<Advertisement> {
+        self.advertisement.clone()
+    }
+
+    pub fn get_resource_pack_manager(&self) -> Arc<ResourcePackManager> {
+        self.resource_pack_manager.clone()
+    }
+
+    pub fn get_level(&self) -> Arc<Level> {
+        self.level.clone()
+    }
+
+    pub fn get_sessions(&self) -> Arc<SessionRegistry> {
+        self.sessions.clone()
+    }
+
+    pub fn get_context(&self) -> ServerContext {
+        ServerContext {
+            properties: self.properties.clone(),
+            advertisement: self.advertisement.clone(),
+            resource_pack_manager: self.resource_pack_manager.clone(),
+            level: self.level.clone(),
+            permissions: self.permissions.clone(),
+            access: self.access.clone(),
+            event_bus: self.event_bus.clone(),
+            sessions: self.sessions.clone(),
+            events: self.session_event_sender.clone(),
+            shutdown: self.shutdown_sender.clone(),
+        }
+    }
+
+    pub fn get_scheduler(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+
+    pub fn get_commands(&self) -> Arc<CommandRegistry> {
+        self.commands.clone()
+    }
+
+    pub fn get_permissions(&self) -> Arc<PermissionManager> {
+        self.permissions.clone()
+    }
+
+    pub fn get_event_bus(&self) -> Arc<EventBus> {
+        self.event_bus.clone()
+    }
+
+    pub fn get_access(&self) -> Arc<AccessControl> {
+        self.access.clone()
+    }
+
+    /// The command permission level of a player, from `ops.json`.
+    pub fn get_permission_level(&self, session: &SessionHandle) -> PermissionLevel {
+        self.permissions.get_permission_level(&session.xuid, &session.display_name)
+    }
+
+    pub fn has_permission(&self, session: &SessionHandle, node: &str) -> bool {
+        self.permissions.has_permission(&session.xuid, &session.display_name, node)
+    }
+
+    /// Sends a player their current abilities and command list, after their permissions
+    /// changed.
+    pub fn refresh_permissions(&self, session: &Arc<SessionHandle>) {
+        let sender = CommandSender::Player(session.clone());
+        session.send(vec![
+            abilities::build(
+                session.runtime_id,
+                self.get_permission_level(session),
+                self.permissions.is_operator(&session.xuid, &session.display_name),
+                self.properties.get_game_type(),
+            ),
+            Packet::AvailableCommands(available_commands::build(&self.commands, |command| sender.can_use(self, command))),
+        ]);
+    }
+
+    pub fn get_plugins(&self) -> &PluginManager {
+        &self.plugins
+    }
+
+    /// Calls `f` with the plugin manager taken out of the server, so plugin hooks can
+    /// borrow the server mutably.
+    fn with_plugins(&mut self, f: impl FnOnce(&mut PluginManager, &mut Server)) {
+        let mut plugins = std::mem::take(&mut self.plugins);
+        f(&mut plugins, self);
+        self.plugins = plugins;
+    }
+
+    pub fn get_console(&self) -> Option<&Console> {
+        self.console.as_ref()
+    }
+
+    pub fn set_console(&mut self, console: Console) {
+        self.console = Some(console);
+    }
+
+    /// Prints `text` on the console, `§` formatting codes included.
+    pub fn print(&self, text: &str) {
+        match &self.console {
+            Some(console) => console.print(text),
+            None => info!("{}", console::format_codes(text, false)),
+        }
+    }
+
+    pub fn get_data_path(&self) -> &str {
+        &self.data_path
+    }
+
+    pub fn get_online_player_count(&self) -> u32 {
+        self.advertisement.get().player_count
+    }
+
+    pub fn set_motd(&self, motd: &str) {
+        self.advertisement.set_motd(motd.to_string());
+    }
+
+    pub fn set_sub_motd(&self, sub_motd: &str) {
+        self.advertisement.set_sub_motd(sub_motd.to_string());
+    }
+
+    pub fn set_default_game_mode(&self, game_mode: Gamemode) {
+        self.advertisement.set_game_mode(game_mode);
     }
 
     pub async fn start(&mut self) -> Result<(), Box<dyn Error>> {
-        println!("start");
+        self.with_plugins(|plugins, server| plugins.enable_all(server));
+        let result = self.tick_processor().await;
+        self.stop().await;
+        result
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    /// Stops the tick loop after the current tick. Listeners stop accepting clients right
+    /// away, sessions are disconnected and data is saved once the loop returned.
+    pub fn shutdown(&mut self, reason: &str) {
+        if !self.is_running {
+            return;
+        }
+        info!("Shutting down: {}", reason);
+        self.is_running = false;
+        self.shutdown_sender.send_replace(Some(reason.to_string()));
+    }
+
+    async fn stop(&mut self) {
+        self.with_plugins(|plugins, server| plugins.disable_all(server));
+        self.scheduler.cancel_all();
+
+        let message = self.properties.shutdown_message.clone();
+        for session in self.sessions.get_all() {
+            session.disconnect(&message);
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+        while !self.sessions.is_empty() && Instant::now() < deadline {
+            sleep(Duration::from_millis(50)).await;
+        }
+        while let Ok(event) = self.session_events.try_recv() {
+            self.handle_session_event(event);
+        }
+        for session in self.sessions.get_all() {
+            warn!("Session of {} did not close in time", session.display_name);
+            self.save_player(&session);
+        }
+
+        let worlds_path = format!("{}/worlds/", self.data_path);
+        match self.level.save(Path::new(&worlds_path)) {
+            Ok(()) => info!("Saved level {}", self.level.name),
+            Err(err) => error!("Failed to save level {}: {}", self.level.name, err),
+        }
+    }
 
-        self.tick_processor().await
+    /// Writes what is known about a player to `players/<uuid>.yml`.
+    fn save_player(&self, session: &SessionHandle) {
+        let file_path = format!("{}/players/{}.yml", self.data_path, session.uuid);
+        let mut config = Config::new(Some(file_path.clone()), ConfigType::Yaml);
+        config.set("name", Value::String(session.display_name.clone()));
+        config.set("xuid", Value::String(session.xuid.clone()));
+        config.set("last-played", Value::String(Utc::now().to_rfc3339()));
+        if let Err(err) = config.save() {
+            error!("Failed to save {}: {}", file_path, err);
+        }
     }
 
+    /// Runs ticks every 50ms. After a lag spike missed ticks run back to back until the
+    /// loop caught up, unless it fell more than `max-catch-up-ticks` behind, in which case
+    /// those ticks are skipped.
     pub async fn tick_processor(&mut self) -> Result<(), Box<dyn Error>> {
+        self.next_tick = Instant::now();
         while self.is_running {
-            match (self.tick().await) {
-                Ok(_) => {}
-                Err(err) => {
-                    println!("{}", err);
-                    return Ok(())
+            tokio::select! {
+                _ = sleep_until(self.next_tick) => {}
+                Ok(()) = self.shutdown_requests.changed() => {
+                    let reason = self.shutdown_requests.borrow_and_update().clone();
+                    if let Some(reason) = reason {
+                        self.shutdown(&reason);
+                    }
+                    continue
                 }
             }
 
-            let next_ms = self.next_tick_ms * 1000;
-            let current_ms = Utc::now().timestamp_micros();
+            if let Err(err) = self.tick().await {
+                error!("{}", err);
+                return Ok(())
+            }
 
-            if next_ms - 100 > current_ms {
-                let allocated = next_ms - current_ms - 1000;
-                if allocated > 0 {
-                    sleep(Duration::from_micros(allocated as u64)).await
-                }
+            self.next_tick += TICK_INTERVAL;
+            let behind = Instant::now().saturating_duration_since(self.next_tick);
+            let behind_ticks = behind.as_millis() / TICK_INTERVAL.as_millis();
+            if behind_ticks > self.properties.max_catch_up_ticks as u128 {
+                self.warn_lag(behind, behind_ticks);
+                self.next_tick = Instant::now();
             }
         }
 
         Ok(())
     }
 
-    pub async fn tick(&mut self) -> Result<(), Box<dyn Error>> {
-        let tick_start = Utc::now().timestamp_millis();
+    fn warn_lag(&mut self, behind: Duration, behind_ticks: u128) {
+        let now = Instant::now();
+        if self.last_lag_warning.is_some_and(|last| now.duration_since(last) < LAG_WARNING_INTERVAL) {
+            return;
+        }
+        self.last_lag_warning = Some(now);
+        warn!(
+            "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+            behind.as_millis(), behind_ticks,
+        );
+    }
 
-        let tick_start_nano = Instant::now();
+    pub async fn tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let tick_start = Instant::now();
 
         self.tick += 1;
+        self.scheduler.advance(self.tick);
 
-        let tick_elapsed_nano = tick_start_nano.elapsed().as_nanos();
-        let tick = f64::min(20.0, 1_000_000_000.0 / f64::max(1_000_000.0, tick_elapsed_nano as f64));
-        let usage = f64::min(1.0, tick_elapsed_nano as f64 / 50_000_000.0);
+        while let Ok(event) = self.session_events.try_recv() {
+            self.handle_session_event(event);
+        }
 
-        if self.usage_max < usage {
-            self.usage_max = usage;
+        let lines = self.console.as_mut().map(Console::take_lines).unwrap_or_default();
+        for line in lines {
+            command::registry::dispatch(self, &CommandSender::Console, &line);
         }
 
-        if self.tick_min > tick {
-            self.tick_min = tick;
+        for mut task in self.scheduler.take_due() {
+            task.run(self);
+            self.scheduler.reschedule(task);
         }
 
-        self.tick_avg.add(tick);
-        self.usage_avg.add(usage);
+        self.with_plugins(|plugins, server| plugins.tick(server));
+        self.event_bus.fire(ServerTickEvent { tick: self.tick });
 
-        if (self.next_tick_ms - tick_start) < -1000 {
-            self.next_tick_ms = tick_start
-        } else { self.next_tick_ms += 50 }
+        let tick_elapsed = tick_start.elapsed();
+        self.tps = match self.last_tick_start {
+            Some(last) => f64::min(TICKS_PER_SECOND, 1.0 / f64::max(0.001, tick_start.duration_since(last).as_secs_f64())),
+            None => TICKS_PER_SECOND,
+        };
+        self.last_tick_start = Some(tick_start);
+        self.tick_usage = f64::min(1.0, tick_elapsed.as_secs_f64() / TICK_INTERVAL.as_secs_f64());
+
+        self.tick_avg.add(self.tps);
+        self.usage_avg.add(self.tick_usage);
 
         if self.tick % 20 == 0 {
-            println!("T: {}, TM: {:.2}, UM: {:.2}, TA: {:.2}, UA: {:.2}", self.tick, self.tick_min, self.usage_max, self.tick_avg.get_avg(), self.usage_avg.get_avg());
+            let (tps, usage) = (self.tick_avg.get_avg(), self.usage_avg.get_avg());
+            self.tps_history.iter_mut().for_each(|history| history.add(tps));
+            self.usage_history.iter_mut().for_each(|history| history.add(usage));
         }
 
         Ok(())
     }
+
+    pub fn get_tick(&self) -> i64 {
+        self.tick
+    }
+
+    /// TPS measured from the time between the last two ticks.
+    pub fn get_tps(&self) -> f64 {
+        self.tps
+    }
+
+    /// Share of the 50ms budget the last tick used, between 0 and 1.
+    pub fn get_tick_usage(&self) -> f64 {
+        self.tick_usage
+    }
+
+    /// TPS averaged over the last 20 ticks.
+    pub fn get_average_tps(&self) -> f64 {
+        self.tick_avg.get_avg()
+    }
+
+    pub fn get_average_tick_usage(&self) -> f64 {
+        self.usage_avg.get_avg()
+    }
+
+    pub fn get_min_tps(&self, window: TickWindow) -> f64 {
+        self.tps_history[window.index()].get_min()
+    }
+
+    pub fn get_max_tps(&self, window: TickWindow) -> f64 {
+        self.tps_history[window.index()].get_max()
+    }
+
+    pub fn get_min_tick_usage(&self, window: TickWindow) -> f64 {
+        self.usage_history[window.index()].get_min()
+    }
+
+    pub fn get_max_tick_usage(&self, window: TickWindow) -> f64 {
+        self.usage_history[window.index()].get_max()
+    }
+
+    fn handle_session_event(&mut self, event: SessionEvent) {
+        match event {
+            SessionEvent::Joined(session) => {
+                info!("{} joined the game", session.display_name);
+                self.refresh_permissions(&session);
+                let event = self.event_bus.fire(PlayerJoinEvent {
+                    join_message: format!("§e{} joined the game", session.display_name),
+                    player: session,
+                });
+                if !event.join_message.is_empty() {
+                    self.sessions.broadcast_message(&event.join_message);
+                }
+            }
+            SessionEvent::Packet(runtime_id, Packet::Text(packet)) if matches!(packet.message_type, TextPacketType::Chat) => {
+                let Some(session) = self.sessions.get(runtime_id) else {
+                    return;
+                };
+                let event = self.event_bus.fire(PlayerChatEvent::new(session, packet.message));
+                if !event.is_cancelled() {
+                    let message = event.get_formatted();
+                    self.sessions.broadcast_message(&message);
+                    self.print(&message);
+                }
+            }
+            SessionEvent::Packet(runtime_id, Packet::CommandRequest(packet)) => {
+                if let Some(session) = self.sessions.get(runtime_id) {
+                    command::registry::dispatch(self, &CommandSender::Player(session), &packet.command);
+                }
+            }
+            // Packets the server does not handle yet.
+            SessionEvent::Packet(_, _) => {}
+            SessionEvent::Left(session) => {
+                info!("{} left the game", session.display_name);
+                self.save_player(&session);
+                let event = self.event_bus.fire(PlayerQuitEvent {
+                    quit_message: format!("§e{} left the game", session.display_name),
+                    player: session,
+                });
+                if !event.quit_message.is_empty() {
+                    self.sessions.broadcast_message(&event.quit_message);
+                }
+            }
+        }
+    }
+}
+
+fn load_content_keys(file_path: &str) -> HashMap<Uuid, String> {
+    let mut config = Config::new(Some(file_path.to_string()), ConfigType::Yaml);
+    if let Err(err) = config.load() {
+        error!("Failed to load {}: {}", file_path, err);
+        return HashMap::new();
+    }
+
+    let mut content_keys = HashMap::new();
+    for key in config.get_keys() {
+        match (Uuid::parse_str(&key), config.get(&key)) {
+            (Ok(uuid), Some(serde_yaml::Value::String(content_key))) => {
+                content_keys.insert(uuid, content_key);
+            }
+            _ => error!("Invalid resource pack key entry {} in {}", key, file_path),
+        }
+    }
+    content_keys
 }
 
 async fn ensure_dir_exists(path: &Path) {
     if !path.exists() {
         if let Err(e) = fs::create_dir_all(path).await {
-            eprintln!("Failed to create directory {:?}: {}", path, e);
+            error!("Failed to create directory {:?}: {}", path, e);
         } else {
-            println!("Directory {:?} created.", path);
+            info!("Created directory {:?}", path);
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,279 @@
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+use bedrockrs::proto::v662::enums::{Difficulty, GameType, Gamemode};
+use bedrockrs::proto::v729::types::experiments::{Experiment, Experiments};
+use log::warn;
+use crate::command::PermissionLevel;
+use serde_yaml::{Mapping, Value};
+use crate::network::pack_cdn::PACK_PATH_PREFIX;
+use crate::resource_pack::resource_pack_manager::DEFAULT_CHUNK_SIZE;
+use crate::utils::config::{Config, ConfigError, ConfigType};
+
+const MIN_CHUNK_SIZE: u32 = 1024;
+const MAX_CHUNK_SIZE: u32 = 1024 * 1024;
+
+static DEFAULTS: &[(&str, &str)] = &[
+    ("motd", "PowerCrabX Server"),
+    ("sub-motd", "Powered by PowerCrabX"),
+    ("server-ip", "0.0.0.0"),
+    ("server-port", "19132"),
+    ("server-ipv6", "::"),
+    ("server-portv6", "19133"),
+    ("max-players", "20"),
+    ("op-permission-level", "4"),
+    ("xbox-auth", "on"),
+    ("white-list", "off"),
+    ("enable-encryption", "on"),
+    ("resource-pack-chunk-size", "102400"),
+    ("force-resource-packs", "off"),
+    ("experiments", ""),
+    ("pack-cdn", "off"),
+    ("pack-cdn-port", "19135"),
+    ("pack-cdn-url", ""),
+    ("level-name", "world"),
+    ("level-seed", "0"),
+    ("gamemode", "survival"),
+    ("difficulty", "normal"),
+    ("view-distance", "8"),
+    ("login-timeout", "10"),
+    ("resource-pack-timeout", "300"),
+    ("spawn-timeout", "60"),
+    ("max-unexpected-packets", "20"),
+    ("max-catch-up-ticks", "100"),
+    ("shutdown-message", "Server closed"),
+];
+
+#[derive(Debug, Clone)]
+pub struct ServerProperties {
+    pub motd: String,
+    pub sub_motd: String,
+    pub server_ip: String,
+    pub server_port: u16,
+    pub server_ipv6: String,
+    pub server_port_v6: u16,
+    pub max_players: u32,
+    /// Command permission level `/op` grants, capped at the level of whoever runs it.
+    pub op_permission_level: u8,
+    pub xbox_auth: bool,
+    /// Only players in `whitelist.json` and operators may join.
+    pub white_list: bool,
+    pub enable_encryption: bool,
+    pub resource_pack_chunk_size: u32,
+    pub force_resource_packs: bool,
+    pub experiments: Vec<String>,
+    pub pack_cdn: bool,
+    pub pack_cdn_port: u16,
+    pub pack_cdn_url: String,
+    pub level_name: String,
+    pub level_seed: u64,
+    pub gamemode: String,
+    pub difficulty: String,
+    pub view_distance: i32,
+    /// Seconds a client may spend in each pre-game stage, `0` disables the limit.
+    pub login_timeout: u64,
+    pub resource_pack_timeout: u64,
+    pub spawn_timeout: u64,
+    pub max_unexpected_packets: u32,
+    /// Ticks the server runs back to back after a lag spike before it skips ahead.
+    pub max_catch_up_ticks: u32,
+    pub shutdown_message: String,
+}
+
+impl Default for ServerProperties {
+    fn default() -> Self {
+        Self {
+            motd: String::from("PowerCrabX Server"),
+            sub_motd: String::from("Powered by PowerCrabX"),
+            server_ip: String::from("0.0.0.0"),
+            server_port: 19132,
+            server_ipv6: String::from("::"),
+            server_port_v6: 19133,
+            max_players: 20,
+            op_permission_level: 4,
+            xbox_auth: true,
+            white_list: false,
+            enable_encryption: true,
+            resource_pack_chunk_size: DEFAULT_CHUNK_SIZE,
+            force_resource_packs: false,
+            experiments: Vec::new(),
+            pack_cdn: false,
+            pack_cdn_port: 19135,
+            pack_cdn_url: String::new(),
+            level_name: String::from("world"),
+            level_seed: 0,
+            gamemode: String::from("survival"),
+            difficulty: String::from("normal"),
+            view_distance: 8,
+            login_timeout: 10,
+            resource_pack_timeout: 300,
+            spawn_timeout: 60,
+            max_unexpected_packets: 20,
+            max_catch_up_ticks: 100,
+            shutdown_message: String::from("Server closed"),
+        }
+    }
+}
+
+impl ServerProperties {
+    /// Reads `server.properties`, adding missing keys. A key with an invalid value is
+    /// reported and falls back to its default, the other keys still apply.
+    pub fn load(file_path: &str) -> Result<ServerProperties, String> {
+        let mut config = Config::new(Some(file_path.to_string()), ConfigType::Properties);
+        config.load()?;
+
+        let defaults: Mapping = DEFAULTS.iter()
+            .map(|(key, value)| (Value::String(key.to_string()), Value::String(value.to_string())))
+            .collect();
+        if config.set_defaults(&defaults)? {
+            config.save()?;
+        }
+
+        let default = ServerProperties::default();
+        let mut resource_pack_chunk_size = get_or_warn(config.parse("resource-pack-chunk-size"), default.resource_pack_chunk_size);
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&resource_pack_chunk_size) {
+            warn!(
+                "resource-pack-chunk-size must be between {} and {}, got {}, using {}",
+                MIN_CHUNK_SIZE, MAX_CHUNK_SIZE, resource_pack_chunk_size, default.resource_pack_chunk_size,
+            );
+            resource_pack_chunk_size = default.resource_pack_chunk_size;
+        }
+        let mut op_permission_level = get_or_warn(config.parse("op-permission-level"), default.op_permission_level);
+        if !(1..=4).contains(&op_permission_level) {
+            warn!("op-permission-level must be between 1 and 4, got {}, using {}", op_permission_level, default.op_permission_level);
+            op_permission_level = default.op_permission_level;
+        }
+        let mut view_distance = get_or_warn(config.parse("view-distance"), default.view_distance);
+        if view_distance < 1 {
+            warn!("view-distance must be at least 1, got {}, using {}", view_distance, default.view_distance);
+            view_distance = default.view_distance;
+        }
+
+        Ok(ServerProperties {
+            motd: config.get_string("motd", &default.motd),
+            sub_motd: config.get_string("sub-motd", &default.sub_motd),
+            server_ip: config.get_string("server-ip", &default.server_ip),
+            server_port: get_or_warn(config.parse("server-port"), default.server_port),
+            server_ipv6: config.get_string("server-ipv6", &default.server_ipv6),
+            server_port_v6: get_or_warn(config.parse("server-portv6"), default.server_port_v6),
+            max_players: get_or_warn(config.parse("max-players"), default.max_players),
+            op_permission_level,
+            xbox_auth: get_or_warn(config.parse_bool("xbox-auth"), default.xbox_auth),
+            white_list: get_or_warn(config.parse_bool("white-list"), default.white_list),
+            enable_encryption: get_or_warn(config.parse_bool("enable-encryption"), default.enable_encryption),
+            resource_pack_chunk_size,
+            force_resource_packs: get_or_warn(config.parse_bool("force-resource-packs"), default.force_resource_packs),
+            experiments: config.get_string_list("experiments"),
+            pack_cdn: get_or_warn(config.parse_bool("pack-cdn"), default.pack_cdn),
+            pack_cdn_port: get_or_warn(config.parse("pack-cdn-port"), default.pack_cdn_port),
+            pack_cdn_url: config.get_string("pack-cdn-url", &default.pack_cdn_url),
+            level_name: config.get_string("level-name", &default.level_name),
+            level_seed: get_or_warn(config.parse("level-seed"), default.level_seed),
+            gamemode: config.get_string("gamemode", &default.gamemode).to_lowercase(),
+            difficulty: config.get_string("difficulty", &default.difficulty).to_lowercase(),
+            view_distance,
+            login_timeout: get_or_warn(config.parse("login-timeout"), default.login_timeout),
+            resource_pack_timeout: get_or_warn(config.parse("resource-pack-timeout"), default.resource_pack_timeout),
+            spawn_timeout: get_or_warn(config.parse("spawn-timeout"), default.spawn_timeout),
+            max_unexpected_packets: get_or_warn(config.parse("max-unexpected-packets"), default.max_unexpected_packets),
+            max_catch_up_ticks: get_or_warn(config.parse("max-catch-up-ticks"), default.max_catch_up_ticks),
+            shutdown_message: config.get_string("shutdown-message", &default.shutdown_message),
+        })
+    }
+
+    /// Experiments sent in both `ResourcePackStack` and `StartGame`, which must agree.
+    pub fn get_experiments(&self) -> Experiments {
+        Experiments {
+            experiments: self.experiments.iter()
+                .map(|name| Experiment {
+                    name: name.clone(),
+                    enabled: true,
+                })
+                .collect(),
+            ever_toggled: !self.experiments.is_empty(),
+        }
+    }
+
+    pub fn get_op_permission_level(&self) -> PermissionLevel {
+        PermissionLevel::from_u8(self.op_permission_level)
+    }
+
+    pub fn get_game_type(&self) -> GameType {
+        match self.gamemode.as_str() {
+            "creative" | "1" => GameType::Creative,
+            "adventure" | "2" => GameType::Adventure,
+            "spectator" | "6" => GameType::Spectator,
+            _ => GameType::Survival,
+        }
+    }
+
+    pub fn get_player_gamemode(&self) -> Gamemode {
+        match self.get_game_type() {
+            GameType::Creative => Gamemode::Creative,
+            GameType::Adventure => Gamemode::Adventure,
+            GameType::Spectator => Gamemode::Spectator,
+            _ => Gamemode::Survival,
+        }
+    }
+
+    pub fn get_difficulty(&self) -> Difficulty {
+        match self.difficulty.as_str() {
+            "peaceful" | "0" => Difficulty::Peaceful,
+            "easy" | "1" => Difficulty::Easy,
+            "hard" | "3" => Difficulty::Hard,
+            _ => Difficulty::Normal,
+        }
+    }
+
+    pub fn pack_cdn_address(&self) -> Result<SocketAddr, String> {
+        socket_address(&self.server_ip, self.pack_cdn_port)
+    }
+
+    /// URL advertised to clients for downloading a pack from the built-in CDN. Empty when
+    /// the CDN is disabled, or when `pack-cdn-url` is unset and the CDN binds to an
+    /// unspecified address clients cannot reach, so they use in-band chunks instead.
+    pub fn get_pack_cdn_url(&self, pack_id: &str) -> String {
+        if !self.pack_cdn {
+            return String::new();
+        }
+        let base_url = if self.pack_cdn_url.is_empty() {
+            if self.is_pack_cdn_unreachable() {
+                return String::new();
+            }
+            format!("http://{}:{}", self.server_ip, self.pack_cdn_port)
+        } else {
+            self.pack_cdn_url.trim_end_matches('/').to_string()
+        };
+        format!("{}{}{}.zip", base_url, PACK_PATH_PREFIX, pack_id)
+    }
+
+    /// Whether clients have no address to reach the CDN at.
+    pub fn is_pack_cdn_unreachable(&self) -> bool {
+        self.pack_cdn_url.is_empty()
+            && self.server_ip.trim().parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+    }
+
+    pub fn ipv4_address(&self) -> Result<SocketAddr, String> {
+        socket_address(&self.server_ip, self.server_port)
+    }
+
+    pub fn ipv6_address(&self) -> Result<SocketAddr, String> {
+        socket_address(&self.server_ipv6, self.server_port_v6)
+    }
+}
+
+fn socket_address(ip: &str, port: u16) -> Result<SocketAddr, String> {
+    let ip: IpAddr = ip.trim().parse().map_err(|e| format!("Invalid bind address {}: {}", ip, e))?;
+    Ok(SocketAddr::new(ip, port))
+}
+
+/// The parsed value, or `default` when it is missing or invalid. Invalid values are
+/// reported so a typo does not go unnoticed.
+fn get_or_warn<T: Display>(value: Result<Option<T>, ConfigError>, default: T) -> T {
+    match value {
+        Ok(value) => value.unwrap_or(default),
+        Err(err) => {
+            warn!("{}, using {}", err, default);
+            default
+        }
+    }
+}
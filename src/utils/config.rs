@@ -1,44 +1,273 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_yaml::{Value, to_string};
-use std::collections::{HashMap, HashSet};
-use std::fs::{File, create_dir_all};
-use std::io::{self, Read, Write};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
-use log::{info, error};
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Why a config could not be loaded, saved or converted.
+#[derive(Debug)]
+pub enum ConfigError {
+    MissingPath,
+    Io(String, io::Error),
+    /// The file is not valid for its format.
+    Parse(String, String),
+    /// A value could not be converted to or from a Rust type.
+    Convert(String, String),
+    UnsupportedType,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingPath => write!(f, "File path is not provided."),
+            ConfigError::Io(file, err) => write!(f, "Failed to access {}: {}", file, err),
+            ConfigError::Parse(file, err) => write!(f, "Failed to parse {}: {}", file, err),
+            ConfigError::Convert(path, err) => write!(f, "Invalid value at {}: {}", path, err),
+            ConfigError::UnsupportedType => write!(f, "Unsupported config type"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<ConfigError> for String {
+    fn from(err: ConfigError) -> Self {
+        err.to_string()
+    }
+}
+
+/// A tree of values. Nested sections are addressed with dotted paths such as
+/// `network.port`, the typed getters fall back to a default when the value is missing or
+/// has the wrong type.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
 pub struct ConfigSection {
-    data: HashMap<String, Value>,
+    data: Mapping,
 }
 
 impl ConfigSection {
     pub fn new() -> Self {
         ConfigSection {
-            data: HashMap::new(),
+            data: Mapping::new(),
         }
     }
 
-    pub fn insert(&mut self, key: String, value: Value) {
-        self.data.insert(key, value);
+    pub fn get(&self, path: &str) -> Option<Value> {
+        self.lookup(path).cloned()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.lookup(path).is_some()
     }
 
-    pub fn get(&self, key: &str) -> Option<&Value> {
-        self.data.get(key)
+    /// Sets a value, creating the sections on the way. A value that is not a section is
+    /// replaced when the path goes through it.
+    pub fn set(&mut self, path: &str, value: Value) {
+        let mut keys = path.split('.').peekable();
+        let mut section = &mut self.data;
+        while let Some(key) = keys.next() {
+            let key = Value::String(key.to_string());
+            if keys.peek().is_none() {
+                section.insert(key, value);
+                return;
+            }
+            let entry = section.entry(key).or_insert_with(|| Value::Mapping(Mapping::new()));
+            if !entry.is_mapping() {
+                *entry = Value::Mapping(Mapping::new());
+            }
+            let Value::Mapping(next) = entry else {
+                unreachable!();
+            };
+            section = next;
+        }
     }
 
-    pub fn get_all(&self) -> &HashMap<String, Value> {
+    pub fn remove(&mut self, path: &str) -> Option<Value> {
+        let (parent, key) = match path.rsplit_once('.') {
+            Some((parent, key)) => (Some(parent), key),
+            None => (None, path),
+        };
+        let section = match parent {
+            Some(parent) => match self.lookup_mut(parent)? {
+                Value::Mapping(section) => section,
+                _ => return None,
+            },
+            None => &mut self.data,
+        };
+        section.remove(key)
+    }
+
+    /// Keys directly in this section.
+    pub fn get_keys(&self) -> HashSet<String> {
+        self.data.keys().filter_map(|key| key.as_str().map(str::to_string)).collect()
+    }
+
+    pub fn get_all(&self) -> &Mapping {
         &self.data
     }
 
-    pub fn keys(&self) -> HashSet<String> {
-        self.data.keys().cloned().collect()
+    /// Integers, and strings holding one, as written in `.properties` files.
+    pub fn get_int(&self, path: &str, default: i64) -> i64 {
+        self.parse(path).ok().flatten().unwrap_or(default)
+    }
+
+    pub fn get_float(&self, path: &str, default: f64) -> f64 {
+        self.parse(path).ok().flatten().unwrap_or(default)
+    }
+
+    /// Booleans, and `on`/`off`, `true`/`false`, `yes`/`no` and `1`/`0` strings.
+    pub fn get_bool(&self, path: &str, default: bool) -> bool {
+        self.parse_bool(path).ok().flatten().unwrap_or(default)
+    }
+
+    /// Parses a scalar, or a string holding one, into `T`. `None` when it is missing, an
+    /// error instead of a default when it does not parse.
+    pub fn parse<T>(&self, path: &str) -> Result<Option<T>, ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let text = match self.lookup(path) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(value) => scalar_to_string(value)
+                .ok_or_else(|| ConfigError::Convert(path.to_string(), String::from("not a scalar")))?,
+        };
+        text.trim().parse()
+            .map(Some)
+            .map_err(|e| ConfigError::Convert(path.to_string(), format!("{} ({})", text.trim(), e)))
+    }
+
+    /// `get_bool` without the default, a value that is not one of its spellings is an error.
+    pub fn parse_bool(&self, path: &str) -> Result<Option<bool>, ConfigError> {
+        let text = match self.lookup(path) {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Bool(value)) => return Ok(Some(*value)),
+            Some(value) => scalar_to_string(value).unwrap_or_default(),
+        };
+        match text.trim().to_lowercase().as_str() {
+            "on" | "true" | "yes" | "1" => Ok(Some(true)),
+            "off" | "false" | "no" | "0" => Ok(Some(false)),
+            _ => Err(ConfigError::Convert(path.to_string(), format!("{} (expected on or off)", text.trim()))),
+        }
+    }
+
+    /// Strings, and numbers or booleans converted to one.
+    pub fn get_string(&self, path: &str, default: &str) -> String {
+        match self.lookup(path) {
+            Some(value) => scalar_to_string(value).unwrap_or_else(|| default.to_string()),
+            None => default.to_string(),
+        }
+    }
+
+    /// A list of scalars, or a comma separated string. Empty when missing.
+    pub fn get_string_list(&self, path: &str) -> Vec<String> {
+        match self.lookup(path) {
+            Some(Value::Sequence(values)) => values.iter().filter_map(scalar_to_string).collect(),
+            Some(Value::String(text)) => text.split(',')
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// A copy of a nested section.
+    pub fn get_section(&self, path: &str) -> Option<ConfigSection> {
+        match self.lookup(path) {
+            Some(Value::Mapping(data)) => Some(ConfigSection { data: data.clone() }),
+            _ => None,
+        }
+    }
+
+    /// Deserializes the value at `path`, `None` when it is missing.
+    pub fn get_as<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>, ConfigError> {
+        match self.lookup(path) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => serde_yaml::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| ConfigError::Convert(path.to_string(), e.to_string())),
+        }
+    }
+
+    /// Deserializes the whole section.
+    pub fn deserialize<T: DeserializeOwned>(&self) -> Result<T, ConfigError> {
+        serde_yaml::from_value(Value::Mapping(self.data.clone()))
+            .map_err(|e| ConfigError::Convert(String::from("<root>"), e.to_string()))
+    }
+
+    /// Serializes `value` into `path`.
+    pub fn set_as<T: Serialize>(&mut self, path: &str, value: &T) -> Result<(), ConfigError> {
+        let value = serde_yaml::to_value(value).map_err(|e| ConfigError::Convert(path.to_string(), e.to_string()))?;
+        self.set(path, value);
+        Ok(())
+    }
+
+    /// Adds every value of `defaults` that is missing here, descending into sections both
+    /// sides have. Returns whether anything was added, so callers know to save.
+    pub fn set_defaults<T: Serialize>(&mut self, defaults: &T) -> Result<bool, ConfigError> {
+        let defaults = match serde_yaml::to_value(defaults) {
+            Ok(Value::Mapping(defaults)) => defaults,
+            Ok(_) => return Err(ConfigError::Convert(String::from("<root>"), String::from("defaults are not a mapping"))),
+            Err(e) => return Err(ConfigError::Convert(String::from("<root>"), e.to_string())),
+        };
+        Ok(merge_defaults(&mut self.data, defaults))
+    }
+
+    fn lookup(&self, path: &str) -> Option<&Value> {
+        let mut keys = path.split('.');
+        let mut value = self.data.get(keys.next()?)?;
+        for key in keys {
+            value = value.as_mapping()?.get(key)?;
+        }
+        Some(value)
+    }
+
+    fn lookup_mut(&mut self, path: &str) -> Option<&mut Value> {
+        let mut keys = path.split('.');
+        let mut value = self.data.get_mut(keys.next()?)?;
+        for key in keys {
+            value = value.as_mapping_mut()?.get_mut(key)?;
+        }
+        Some(value)
     }
 }
 
+fn merge_defaults(data: &mut Mapping, defaults: Mapping) -> bool {
+    let mut changed = false;
+    for (key, default) in defaults {
+        match (data.get_mut(&key), default) {
+            (Some(Value::Mapping(section)), Value::Mapping(defaults)) => {
+                changed |= merge_defaults(section, defaults);
+            }
+            (Some(_), _) => {}
+            (None, default) => {
+                data.insert(key, default);
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// A `ConfigSection` backed by a file. Derefs to its root section for reading and
+/// writing values.
 pub struct Config {
-    config: Arc<Mutex<ConfigSection>>,
+    root: ConfigSection,
     file: Option<String>,
     file_type: ConfigType,
 }
@@ -52,98 +281,283 @@ pub enum ConfigType {
     Detect,
 }
 
+impl Deref for Config {
+    type Target = ConfigSection;
+
+    fn deref(&self) -> &ConfigSection {
+        &self.root
+    }
+}
+
+impl DerefMut for Config {
+    fn deref_mut(&mut self) -> &mut ConfigSection {
+        &mut self.root
+    }
+}
+
 impl Config {
     pub fn new(file: Option<String>, file_type: ConfigType) -> Self {
+        let file_type = match file_type {
+            ConfigType::Detect => detect_type(file.as_deref()),
+            file_type => file_type,
+        };
         Config {
-            config: Arc::new(Mutex::new(ConfigSection::new())),
+            root: ConfigSection::new(),
             file,
-            file_type: if file_type == ConfigType::Detect { ConfigType::Yaml } else { file_type },
+            file_type,
         }
     }
 
-    pub fn load(&mut self) -> Result<(), String> {
-        let file = self.file.clone().ok_or("File path is not provided.")?;
+    /// Reads the file, creating an empty one if it does not exist yet.
+    pub fn load(&mut self) -> Result<(), ConfigError> {
+        let file = self.file.clone().ok_or(ConfigError::MissingPath)?;
         let path = Path::new(&file);
         if !path.exists() {
-            create_dir_all(path.parent().unwrap()).unwrap();
-            File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).map_err(|e| ConfigError::Io(parent.display().to_string(), e))?;
+            }
             self.save()?;
             return Ok(());
         }
 
-        let mut content = String::new();
-        let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
-        file.read_to_string(&mut content).map_err(|e| format!("Failed to read file: {}", e))?;
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::Io(file.clone(), e))?;
+        self.root = match self.file_type {
+            ConfigType::Properties => parse_properties(&content),
+            ConfigType::Json => parse_json(&content).map_err(|e| ConfigError::Parse(file, e))?,
+            ConfigType::Yaml => parse_yaml(&content).map_err(|e| ConfigError::Parse(file, e))?,
+            _ => return Err(ConfigError::UnsupportedType),
+        };
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<(), ConfigError> {
+        let file = self.file.clone().ok_or(ConfigError::MissingPath)?;
 
-        match self.file_type {
-            ConfigType::Properties => self.parse_properties(&content),
-            ConfigType::Json => self.parse_json(&content),
-            ConfigType::Yaml => self.parse_yaml(&content),
-            _ => Err("Unsupported config type".to_string()),
-        }
+        let content = match self.file_type {
+            ConfigType::Properties => write_properties(&self.root.data),
+            ConfigType::Json => serde_json::to_string_pretty(&self.root)
+                .map_err(|e| ConfigError::Convert(file.clone(), e.to_string()))?,
+            ConfigType::Yaml => serde_yaml::to_string(&self.root)
+                .map_err(|e| ConfigError::Convert(file.clone(), e.to_string()))?,
+            _ => return Err(ConfigError::UnsupportedType),
+        };
+
+        fs::write(&file, content).map_err(|e| ConfigError::Io(file, e))
     }
+}
 
-    fn parse_properties(&mut self, content: &str) {
-        // Parse properties format (key=value)
-        for line in content.lines() {
-            let parts: Vec<&str> = line.splitn(2, '=').collect();
-            if parts.len() == 2 {
-                let key = parts[0].to_string();
-                let value = Value::String(parts[1].to_string());
-                self.config.lock().unwrap().insert(key, value);
-            }
+fn detect_type(file: Option<&str>) -> ConfigType {
+    let extension = file
+        .and_then(|file| Path::new(file).extension())
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("properties") => ConfigType::Properties,
+        Some("json") => ConfigType::Json,
+        _ => ConfigType::Yaml,
+    }
+}
+
+/// `key=value` lines, dotted keys become nested sections.
+fn parse_properties(content: &str) -> ConfigSection {
+    let mut section = ConfigSection::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            section.set(key.trim(), Value::String(value.trim().to_string()));
         }
     }
+    section
+}
 
-    fn parse_json(&mut self, content: &str) {
-        let config_section: HashMap<String, Value> = serde_json::from_str(content).unwrap();
-        self.config.lock().unwrap().data = config_section;
+fn parse_json(content: &str) -> Result<ConfigSection, String> {
+    if content.trim().is_empty() {
+        return Ok(ConfigSection::new());
     }
+    serde_json::from_str(content).map_err(|e| e.to_string())
+}
 
-    fn parse_yaml(&mut self, content: &str) {
-        let config_section: HashMap<String, Value> = serde_yaml::from_str(content).unwrap();
-        self.config.lock().unwrap().data = config_section;
+fn parse_yaml(content: &str) -> Result<ConfigSection, String> {
+    match serde_yaml::from_str(content).map_err(|e| e.to_string())? {
+        Value::Null => Ok(ConfigSection::new()),
+        Value::Mapping(data) => Ok(ConfigSection { data }),
+        _ => Err(String::from("the document is not a mapping")),
     }
+}
 
-    pub fn save(&self) -> Result<(), String> {
-        let file = self.file.clone().ok_or("File path is not provided.")?;
-        let path = Path::new(&file);
-        let mut file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+fn write_properties(data: &Mapping) -> String {
+    let mut content = String::new();
+    write_properties_section(&mut content, "", data);
+    content
+}
 
-        let content = match self.file_type {
-            ConfigType::Properties => self.write_properties(),
-            ConfigType::Json => serde_json::to_string_pretty(&*self.config.lock().unwrap()).unwrap(),
-            ConfigType::Yaml => to_string(&*self.config.lock().unwrap()).unwrap(),
-            _ => return Err("Unsupported config type".to_string()),
+fn write_properties_section(content: &mut String, prefix: &str, data: &Mapping) {
+    for (key, value) in data {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let key = if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+        let value_str = match value {
+            Value::Mapping(section) => {
+                write_properties_section(content, &key, section);
+                continue;
+            }
+            Value::Bool(b) => String::from(if *b { "on" } else { "off" }),
+            Value::Sequence(values) => values.iter().filter_map(scalar_to_string).collect::<Vec<_>>().join(","),
+            Value::Null => String::new(),
+            value => scalar_to_string(value).unwrap_or_default(),
         };
+        content.push_str(&format!("{}={}\n", key, value_str));
+    }
+}
 
-        file.write_all(content.as_bytes())
-            .map_err(|e| format!("Failed to write file: {}", e))?;
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("powercrabx-config-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
     }
 
-    fn write_properties(&self) -> String {
-        let mut content = String::new();
-        for (key, value) in &*self.config.lock().unwrap().get_all() {
-            let value_str = match value {
-                Value::Bool(b) => if *b { "on" } else { "off" },
-                Value::String(s) => &s,
-                _ => "unknown",
-            };
-            content.push_str(&format!("{}={}\n", key, value_str));
+    fn load(name: &str, content: &str) -> Result<Config, ConfigError> {
+        let path = temp_file(name, content);
+        let mut config = Config::new(Some(path.to_string_lossy().to_string()), ConfigType::Detect);
+        let result = config.load();
+        let _ = fs::remove_file(&path);
+        result.map(|_| config)
+    }
+
+    #[test]
+    fn set_creates_sections_along_the_path() {
+        let mut section = ConfigSection::new();
+        section.set("network.port", Value::from(19132));
+        section.set("network.ipv6.enabled", Value::from(true));
+
+        assert_eq!(section.get_int("network.port", 0), 19132);
+        assert!(section.get_bool("network.ipv6.enabled", false));
+        assert!(section.get_section("network.ipv6").is_some());
+        assert_eq!(section.get_keys(), HashSet::from([String::from("network")]));
+
+        // A path through a scalar replaces it with a section.
+        section.set("network.port.v4", Value::from(1));
+        assert_eq!(section.get_int("network.port.v4", 0), 1);
+        assert!(section.get_section("network.port").is_some());
+    }
+
+    #[test]
+    fn lookup_stops_at_missing_keys_and_scalars() {
+        let mut section = ConfigSection::new();
+        section.set("motd", Value::from("Hello"));
+        assert!(!section.contains("missing"));
+        assert!(!section.contains("motd.sub"));
+        assert_eq!(section.get_string("motd.sub", "default"), "default");
+    }
+
+    #[test]
+    fn remove_takes_nested_values() {
+        let mut section = ConfigSection::new();
+        section.set("network.port", Value::from(19132));
+        section.set("motd", Value::from("Hello"));
+
+        assert_eq!(section.remove("network.port"), Some(Value::from(19132)));
+        assert!(!section.contains("network.port"));
+        assert!(section.contains("network"));
+        assert_eq!(section.remove("network.port"), None);
+        assert_eq!(section.remove("motd.sub"), None);
+        assert_eq!(section.remove("motd"), Some(Value::from("Hello")));
+    }
+
+    #[test]
+    fn parse_reports_invalid_values() {
+        let mut section = ConfigSection::new();
+        section.set("port", Value::from(" 19132 "));
+        section.set("players", Value::from(20));
+        section.set("invalid", Value::from("abc"));
+        section.set("too-large", Value::from("70000"));
+        section.set("list", Value::Sequence(vec![Value::from(1)]));
+
+        assert_eq!(section.parse::<u16>("port").unwrap(), Some(19132));
+        assert_eq!(section.parse::<u32>("players").unwrap(), Some(20));
+        assert_eq!(section.parse::<u16>("missing").unwrap(), None);
+        assert!(matches!(section.parse::<u16>("invalid"), Err(ConfigError::Convert(path, _)) if path == "invalid"));
+        assert!(matches!(section.parse::<u16>("too-large"), Err(ConfigError::Convert(_, _))));
+        assert!(matches!(section.parse::<u16>("list"), Err(ConfigError::Convert(_, _))));
+        assert_eq!(section.get_int("invalid", 7), 7);
+    }
+
+    #[test]
+    fn parse_bool_accepts_every_spelling() {
+        let mut section = ConfigSection::new();
+        for (value, expected) in [("on", true), ("TRUE", true), ("Yes", true), ("1", true), ("off", false), ("False", false), ("no", false), ("0", false)] {
+            section.set("flag", Value::from(value));
+            assert_eq!(section.parse_bool("flag").unwrap(), Some(expected), "{}", value);
+            assert_eq!(section.get_bool("flag", !expected), expected, "{}", value);
         }
-        content
+        section.set("flag", Value::from(true));
+        assert_eq!(section.parse_bool("flag").unwrap(), Some(true));
+
+        section.set("flag", Value::from("maybe"));
+        assert!(matches!(section.parse_bool("flag"), Err(ConfigError::Convert(_, _))));
+        assert!(section.get_bool("flag", true));
+        assert_eq!(section.parse_bool("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn set_defaults_only_adds_missing_values() {
+        let mut section = ConfigSection::new();
+        section.set("motd", Value::from("Custom"));
+        section.set("network.port", Value::from(25565));
+
+        let mut defaults = Mapping::new();
+        defaults.insert(Value::from("motd"), Value::from("Default"));
+        let mut network = Mapping::new();
+        network.insert(Value::from("port"), Value::from(19132));
+        network.insert(Value::from("ipv6"), Value::from(false));
+        defaults.insert(Value::from("network"), Value::Mapping(network));
+
+        assert!(section.set_defaults(&defaults).unwrap());
+        assert_eq!(section.get_string("motd", ""), "Custom");
+        assert_eq!(section.get_int("network.port", 0), 25565);
+        assert!(section.contains("network.ipv6"));
+        assert!(!section.set_defaults(&defaults).unwrap());
+
+        assert!(matches!(section.set_defaults(&vec![1]), Err(ConfigError::Convert(_, _))));
     }
 
-    pub fn set(&mut self, key: &str, value: Value) {
-        self.config.lock().unwrap().insert(key.to_string(), value);
+    #[test]
+    fn properties_round_trip_nested_keys() {
+        let content = "motd=Hello\n# comment\n\nnetwork.port = 19132\nnetwork.ipv6.enabled=on\nexperiments=a,b\n";
+        let section = parse_properties(content);
+        assert_eq!(section.get_int("network.port", 0), 19132);
+        assert!(section.get_bool("network.ipv6.enabled", false));
+        assert_eq!(section.get_string_list("experiments"), vec![String::from("a"), String::from("b")]);
+
+        let written = write_properties(&section.data);
+        assert_eq!(written, "motd=Hello\nnetwork.port=19132\nnetwork.ipv6.enabled=on\nexperiments=a,b\n");
+        assert_eq!(parse_properties(&written).data, section.data);
     }
 
-    pub fn get(&self, key: &str) -> Option<Value> {
-        self.config.lock().unwrap().get(key).cloned()
+    #[test]
+    fn malformed_files_are_parse_errors() {
+        assert!(matches!(load("broken.json", "{ \"motd\": "), Err(ConfigError::Parse(_, _))));
+        assert!(matches!(load("broken.yml", "motd: [unclosed"), Err(ConfigError::Parse(_, _))));
+        assert!(matches!(load("list.yml", "- a\n- b\n"), Err(ConfigError::Parse(_, _))));
+
+        let config = load("valid.json", "{ \"network\": { \"port\": 19132 } }").unwrap();
+        assert_eq!(config.get_int("network.port", 0), 19132);
     }
 
-    pub fn get_keys(&self) -> HashSet<String> {
-        self.config.lock().unwrap().keys()
+    #[test]
+    fn missing_path_is_an_error() {
+        let mut config = Config::new(None, ConfigType::Yaml);
+        assert!(matches!(config.load(), Err(ConfigError::MissingPath)));
+        assert!(matches!(config.save(), Err(ConfigError::MissingPath)));
     }
-}
\ No newline at end of file
+}
@@ -1,2 +1,3 @@
+pub mod config;
 pub mod rolling_float_average;
 pub mod sem_version;
\ No newline at end of file
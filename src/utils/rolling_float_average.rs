@@ -31,4 +31,12 @@ impl RollingFloatAverage {
             true => 0.0
         }
     }
+
+    pub fn get_min(&self) -> f64 {
+        self.queue.iter().copied().reduce(f64::min).unwrap_or(0.0)
+    }
+
+    pub fn get_max(&self) -> f64 {
+        self.queue.iter().copied().reduce(f64::max).unwrap_or(0.0)
+    }
 }
\ No newline at end of file
@@ -11,4 +11,24 @@ impl SemVersion {
     pub fn new(major: i32, minor: i32, patch: i32, revision: i32, build: i32) -> Self {
         Self { major, minor, patch, revision, build }
     }
-}
\ No newline at end of file
+
+    /// Parses `major.minor.patch`, missing parts are `0`. Anything after a `-` or `+` is
+    /// ignored.
+    pub fn parse(version: &str) -> Option<SemVersion> {
+        let version = version.trim().split(['-', '+']).next()?;
+        let mut parts = [0; 3];
+        for (index, part) in version.split('.').enumerate() {
+            *parts.get_mut(index)? = part.parse().ok()?;
+        }
+        Some(SemVersion::new(parts[0], parts[1], parts[2], 0, 0))
+    }
+
+    /// Whether something written against `self` runs on `other`: the same major version,
+    /// and the same minor version before 1.0, with `other` not older than `self`.
+    pub fn is_compatible_with(&self, other: &SemVersion) -> bool {
+        if self.major != other.major || (self.major == 0 && self.minor != other.minor) {
+            return false;
+        }
+        (self.minor, self.patch) <= (other.minor, other.patch)
+    }
+}